@@ -0,0 +1,310 @@
+//! This module splits data into content-defined chunks, for deduplicated storage.
+//!
+//! The chunk boundaries are chosen by a rolling hash over the content, not by position, so
+//! inserting or removing bytes only changes the chunks around the edit: the chunks after it
+//! keep their boundaries and digests, just at a different offset.
+//!
+//! Two chunkers are available:
+//! 1. FastCDC (Xia et al., 2016), a Gear hash with normalized chunking. It is fast and keeps
+//!    the chunk sizes close to the average.
+//! 2. Buzhash, a cyclic polynomial rolling hash over a 48 byte window, in the style of the
+//!    Rabin fingerprint chunkers of LBFS and rsync.
+//!
+//! ```
+//! use estahr::chunking::{self, ChunkerOptions};
+//! use estahr::hash::HashAlgorithm;
+//!
+//! let data = vec![7; 100_000];
+//! let options = ChunkerOptions::new().hash_algorithm(HashAlgorithm::SHA2_256);
+//! let mut total = 0;
+//! for chunk in chunking::chunk_reader(&data[..], &options).unwrap() {
+//!     let (offset, length, digest) = chunk.unwrap();
+//!     assert_eq!(offset, total);
+//!     total += length as u64;
+//! }
+//! assert_eq!(total, 100_000);
+//! ```
+//!
+//! The tables of both rolling hashes are fixed, so the same content and options always give
+//! the same chunks, across runs and versions of this library.
+
+use std::{fs, io};
+use std::path::Path;
+
+use crate::hash::{hash_bytes, Digest, HashAlgorithm};
+
+const BUZHASH_WINDOW: usize = 48;
+
+/// The most bytes asked of the reader at once, so the buffer grows with the input instead of
+/// being allocated for the maximum chunk size up front.
+const READ_SIZE: usize = 64 * 1024;
+
+/// Builds a table of 256 pseudo random values with SplitMix64, from a fixed seed.
+const fn random_table(seed: u64) -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut state = seed;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+static GEAR: [u64; 256] = random_table(0x4745_4152);
+static BUZHASH: [u64; 256] = random_table(0x4255_5a48);
+
+/// The rolling hash used to find the chunk boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkerAlgorithm {
+    /// FastCDC, with normalized chunking.
+    FastCdc,
+    /// Buzhash over a 48 byte window.
+    Buzhash,
+}
+
+/// Options for splitting data into chunks with `chunk_reader`.
+///
+/// The average size is rounded down to a power of two. No chunk is smaller than the minimum
+/// size, except the last one, and no chunk is larger than the maximum size.
+///
+/// ```
+/// use estahr::chunking::{ChunkerAlgorithm, ChunkerOptions};
+/// use estahr::hash::HashAlgorithm;
+///
+/// let options = ChunkerOptions::new()
+///     .algorithm(ChunkerAlgorithm::Buzhash)
+///     .min_size(16 * 1024)
+///     .avg_size(64 * 1024)
+///     .max_size(256 * 1024)
+///     .hash_algorithm(HashAlgorithm::SHA2_256);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChunkerOptions {
+    algorithm: ChunkerAlgorithm,
+    min_size: usize,
+    avg_size: usize,
+    max_size: usize,
+    hash_algorithm: HashAlgorithm,
+}
+
+impl Default for ChunkerOptions {
+    fn default() -> Self {
+        ChunkerOptions {
+            algorithm: ChunkerAlgorithm::FastCdc,
+            min_size: 2 * 1024,
+            avg_size: 8 * 1024,
+            max_size: 64 * 1024,
+            hash_algorithm: HashAlgorithm::BLAKE3,
+        }
+    }
+}
+
+impl ChunkerOptions {
+    /// The default options: FastCDC with chunks of 2 KiB to 64 KiB, 8 KiB on average, hashed
+    /// with BLAKE3.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the rolling hash used to find the chunk boundaries.
+    pub fn algorithm(mut self, algorithm: ChunkerAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets the minimum chunk size in bytes.
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Sets the average chunk size in bytes.
+    pub fn avg_size(mut self, avg_size: usize) -> Self {
+        self.avg_size = avg_size;
+        self
+    }
+
+    /// Sets the maximum chunk size in bytes.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the hash algorithm of the chunk digests.
+    pub fn hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.min_size == 0 || self.min_size > self.avg_size || self.avg_size > self.max_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                format!("chunk sizes must satisfy 0 < min ({}) <= avg ({}) <= max ({})",
+                        self.min_size, self.avg_size, self.max_size)));
+        }
+        Ok(())
+    }
+}
+
+/// A mask with the `bits` highest bits set.
+fn high_mask(bits: u32) -> u64 {
+    match bits {
+        0 => 0,
+        bits => !0 << (64 - bits.min(64)),
+    }
+}
+
+/// The length of the first chunk of `data`. `data` holds at least `max` bytes, unless it is
+/// the end of the input.
+fn fastcdc_cut(data: &[u8], min: usize, avg: usize, max: usize) -> usize {
+    if data.len() <= min {
+        return data.len();
+    }
+    let end = data.len().min(max);
+    let normal = avg.min(end);
+    // Normalized chunking: a harder condition before the average size, and an easier one
+    // after it, keeps most chunks close to the average.
+    let bits = avg.trailing_zeros();
+    let mask_small = high_mask(bits + 1);
+    let mask_large = high_mask(bits.saturating_sub(1));
+
+    let mut hash = 0u64;
+    for (i, &byte) in data.iter().enumerate().take(end).skip(min) {
+        hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+        let mask = if i < normal { mask_small } else { mask_large };
+        if hash & mask == 0 {
+            return i + 1;
+        }
+    }
+    end
+}
+
+/// The length of the first chunk of `data`, see `fastcdc_cut`.
+fn buzhash_cut(data: &[u8], min: usize, avg: usize, max: usize) -> usize {
+    if data.len() <= min {
+        return data.len();
+    }
+    let end = data.len().min(max);
+    let mask = (avg as u64) - 1;
+    // Only the window before the minimum size has to be hashed to know the hash there.
+    let start = min.saturating_sub(BUZHASH_WINDOW);
+
+    let mut hash = 0u64;
+    for i in start..end {
+        hash = hash.rotate_left(1) ^ BUZHASH[data[i] as usize];
+        if i >= start + BUZHASH_WINDOW {
+            hash ^= BUZHASH[data[i - BUZHASH_WINDOW] as usize].rotate_left(BUZHASH_WINDOW as u32);
+        }
+        if i >= min && hash & mask == 0 {
+            return i + 1;
+        }
+    }
+    end
+}
+
+/// An iterator over the chunks of a reader, returned by `chunk_reader`.
+///
+/// Each item is the offset of the chunk in the input, its length and its digest. At most the
+/// maximum chunk size plus 64 KiB is buffered, and less for smaller inputs. A read error is
+/// returned once, and ends the iteration.
+pub struct Chunks<R> {
+    reader: R,
+    options: ChunkerOptions,
+    buffer: Vec<u8>,
+    start: usize,
+    offset: u64,
+    eof: bool,
+    failed: bool,
+}
+
+impl<R: io::Read> Chunks<R> {
+    /// Reads until the buffer holds a whole maximum size chunk, or the input ends.
+    fn fill(&mut self) -> io::Result<()> {
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        let max = self.options.max_size;
+        while !self.eof && self.buffer.len() < max {
+            let len = self.buffer.len();
+            self.buffer.resize(len + (max - len).min(READ_SIZE), 0);
+            let read = loop {
+                match self.reader.read(&mut self.buffer[len..]) {
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    result => break result,
+                }
+            };
+            match read {
+                Ok(read) => {
+                    self.buffer.truncate(len + read);
+                    self.eof = read == 0;
+                }
+                Err(e) => {
+                    self.buffer.truncate(len);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: io::Read> Iterator for Chunks<R> {
+    type Item = io::Result<(u64, usize, Digest)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        if self.buffer.len() - self.start < self.options.max_size && !self.eof {
+            if let Err(e) = self.fill() {
+                self.failed = true;
+                return Some(Err(e));
+            }
+        }
+        let data = &self.buffer[self.start..];
+        if data.is_empty() {
+            return None;
+        }
+        let options = &self.options;
+        let avg = 1 << (usize::BITS - 1 - options.avg_size.leading_zeros());
+        let length = match options.algorithm {
+            ChunkerAlgorithm::FastCdc => fastcdc_cut(data, options.min_size, avg, options.max_size),
+            ChunkerAlgorithm::Buzhash => buzhash_cut(data, options.min_size, avg, options.max_size),
+        };
+        let digest = hash_bytes(options.hash_algorithm, &data[..length]);
+        let offset = self.offset;
+        self.start += length;
+        self.offset += length as u64;
+        Some(Ok((offset, length, digest)))
+    }
+}
+
+/// Splits everything that can be read from the given reader into content-defined chunks.
+///
+/// The reader is read lazily, as the iterator advances. Returns an `io::Error` of kind
+/// `InvalidInput` if the chunk sizes in the options are not ordered.
+pub fn chunk_reader<R: io::Read>(reader: R, options: &ChunkerOptions) -> io::Result<Chunks<R>> {
+    options.check()?;
+    Ok(Chunks {
+        reader,
+        options: options.clone(),
+        buffer: Vec::new(),
+        start: 0,
+        offset: 0,
+        eof: false,
+        failed: false,
+    })
+}
+
+/// Splits the given file into content-defined chunks, see `chunk_reader`.
+///
+/// Returns an `io::Error` if the file cannot be opened for any reason.
+pub fn chunk_file<P: AsRef<Path>>(path: P, options: &ChunkerOptions) -> io::Result<Chunks<fs::File>> {
+    chunk_reader(fs::File::open(path)?, options)
+}
@@ -0,0 +1,168 @@
+use std::io;
+
+use super::{Digest, HashAlgorithm, Hasher};
+
+fn mismatch(expected: &Digest, actual: &Digest) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData,
+                   format!("digest mismatch: expected {} {}, got {} {}",
+                           expected.algorithm(), expected, actual.algorithm(), actual))
+}
+
+/// An `io::Read` adapter that hashes everything read through it.
+///
+/// The bytes are passed through unchanged, so the data can be copied somewhere and hashed in
+/// a single pass. With an expected digest, the read that reaches EOF returns an
+/// `io::Error` of kind `InvalidData` if the digest does not match.
+///
+/// ```
+/// use std::io;
+/// use estahr::hash::{hash_string, HashAlgorithm, HashingReader};
+///
+/// let expected = hash_string(HashAlgorithm::SHA2_256, "hello world");
+/// let mut reader = HashingReader::new(HashAlgorithm::SHA2_256, &b"hello world"[..]).expect(expected.clone());
+/// let mut copy = Vec::new();
+/// io::copy(&mut reader, &mut copy).unwrap();
+/// assert_eq!(reader.finalize(), expected);
+/// ```
+#[derive(Debug)]
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: Hasher,
+    expected: Option<Digest>,
+}
+
+impl<R: io::Read> HashingReader<R> {
+    /// Wraps the reader, hashing with the selected hash algorithm.
+    pub fn new(hash_algorithm: HashAlgorithm, inner: R) -> HashingReader<R> {
+        HashingReader { inner, hasher: Hasher::new(hash_algorithm), expected: None }
+    }
+
+    /// Sets the digest the data must have, checked when the reader reaches EOF.
+    pub fn expect(mut self, expected: Digest) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// The digest of everything read so far.
+    pub fn finalize(&self) -> Digest {
+        self.hasher.finalize()
+    }
+
+    /// The wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// The wrapped reader. Reading from it directly skips the hashing.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped reader and the digest of everything read through it.
+    pub fn into_inner(self) -> (R, Digest) {
+        let digest = self.hasher.finalize();
+        (self.inner, digest)
+    }
+}
+
+impl<R: io::Read> io::Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read > 0 {
+            self.hasher.update(&buf[..read]);
+        } else if !buf.is_empty() {
+            if let Some(expected) = &self.expected {
+                let actual = self.hasher.finalize();
+                if !actual.ct_eq(expected) {
+                    return Err(mismatch(expected, &actual));
+                }
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// An `io::Write` adapter that hashes everything written through it.
+///
+/// The bytes are passed on to the wrapped writer, and only the bytes it accepts are hashed.
+/// `finish` flushes the writer and returns the digest, or an `io::Error` of kind
+/// `InvalidData` if an expected digest was set and does not match.
+///
+/// ```
+/// use std::io::Write;
+/// use estahr::hash::{hash_string, HashAlgorithm, HashingWriter};
+///
+/// let mut writer = HashingWriter::new(HashAlgorithm::BLAKE3, Vec::new());
+/// writer.write_all(b"hello world").unwrap();
+/// let (copy, digest) = writer.finish().unwrap();
+/// assert_eq!(copy, b"hello world");
+/// assert_eq!(digest, hash_string(HashAlgorithm::BLAKE3, "hello world"));
+/// ```
+#[derive(Debug)]
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Hasher,
+    expected: Option<Digest>,
+}
+
+impl<W: io::Write> HashingWriter<W> {
+    /// Wraps the writer, hashing with the selected hash algorithm.
+    pub fn new(hash_algorithm: HashAlgorithm, inner: W) -> HashingWriter<W> {
+        HashingWriter { inner, hasher: Hasher::new(hash_algorithm), expected: None }
+    }
+
+    /// Sets the digest the data must have, checked by `finish`.
+    pub fn expect(mut self, expected: Digest) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// The digest of everything written so far.
+    pub fn finalize(&self) -> Digest {
+        self.hasher.finalize()
+    }
+
+    /// The wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The wrapped writer. Writing to it directly skips the hashing.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped writer and the digest of everything written through it, without
+    /// checking the expected digest.
+    pub fn into_inner(self) -> (W, Digest) {
+        let digest = self.hasher.finalize();
+        (self.inner, digest)
+    }
+
+    /// Flushes the wrapped writer, and returns it with the digest of everything written.
+    ///
+    /// Returns an `io::Error` if the flush fails, or of kind `InvalidData` if the digest does
+    /// not match the expected one.
+    pub fn finish(mut self) -> io::Result<(W, Digest)> {
+        self.inner.flush()?;
+        let digest = self.hasher.finalize();
+        if let Some(expected) = &self.expected {
+            if !digest.ct_eq(expected) {
+                return Err(mismatch(expected, &digest));
+            }
+        }
+        Ok((self.inner, digest))
+    }
+}
+
+impl<W: io::Write> io::Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
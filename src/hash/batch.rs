@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::{io, panic, thread};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+
+use super::{hash_file_with_options, CancelToken, Digest, HashAlgorithm, HashOptions};
+
+type Job = (usize, PathBuf);
+type JobResult = (usize, PathBuf, io::Result<Digest>);
+
+/// Options for hashing many files with `hash_files_with_options`.
+///
+/// ```
+/// use estahr::hash::BatchOptions;
+///
+/// let options = BatchOptions::new().threads(4).ordered(false);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    threads: usize,
+    ordered: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            ordered: true,
+        }
+    }
+}
+
+impl BatchOptions {
+    /// The default options: one thread per available core, results in input order.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of worker threads. A count of 0 is treated as 1.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// When true, the results come back in the same order as the paths. When false, they come
+    /// back as soon as each file is hashed.
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+}
+
+/// An iterator over the results of hashing many files, returned by `hash_files`.
+///
+/// Paths are pulled from the input iterator as the workers need them, so at most a few
+/// files per thread are in flight at any time. Dropping this iterator cancels the files being
+/// hashed, skips the queued ones and waits for the workers to stop.
+pub struct BatchResults<I> {
+    paths: I,
+    jobs: Option<mpsc::SyncSender<Job>>,
+    results: mpsc::Receiver<JobResult>,
+    workers: Vec<thread::JoinHandle<()>>,
+    cancel_token: CancelToken,
+    ordered: bool,
+    submitted: usize,
+    in_flight: usize,
+    max_in_flight: usize,
+    next_to_yield: usize,
+    pending: BTreeMap<usize, (PathBuf, io::Result<Digest>)>,
+}
+
+impl<I, P> BatchResults<I> where I: Iterator<Item=P>, P: AsRef<Path> {
+    fn submit_jobs(&mut self) {
+        while self.in_flight + self.pending.len() < self.max_in_flight {
+            let sender = match &self.jobs {
+                Some(sender) => sender,
+                None => return,
+            };
+            match self.paths.next() {
+                Some(path) => {
+                    // The channel is as deep as `max_in_flight`, so this never blocks.
+                    if sender.send((self.submitted, path.as_ref().to_path_buf())).is_err() {
+                        return;
+                    }
+                    self.submitted += 1;
+                    self.in_flight += 1;
+                }
+                None => self.jobs = None,
+            }
+        }
+    }
+}
+
+impl<I, P> Iterator for BatchResults<I> where I: Iterator<Item=P>, P: AsRef<Path> {
+    type Item = (PathBuf, io::Result<Digest>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.submit_jobs();
+            if let Some(result) = self.pending.remove(&self.next_to_yield) {
+                self.next_to_yield += 1;
+                return Some(result);
+            }
+            if self.in_flight == 0 {
+                return None;
+            }
+            let (index, path, result) = self.results.recv().ok()?;
+            self.in_flight -= 1;
+            if !self.ordered {
+                return Some((path, result));
+            }
+            self.pending.insert(index, (path, result));
+        }
+    }
+}
+
+impl<I> Drop for BatchResults<I> {
+    fn drop(&mut self) {
+        self.cancel_token.cancel();
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Hashes many files on a pool of worker threads, with the default `BatchOptions`.
+///
+/// See `hash_files_with_options`.
+pub fn hash_files<I, P>(hash_algorithm: HashAlgorithm, paths: I) -> BatchResults<I::IntoIter>
+    where I: IntoIterator<Item=P>, P: AsRef<Path> {
+    hash_files_with_options(hash_algorithm, paths, &BatchOptions::default())
+}
+
+/// Hashes many files on a pool of worker threads.
+///
+/// Returns an iterator of `(path, result)` pairs, one per input path. A file that cannot be
+/// hashed yields its `io::Error` and does not stop the others, and so does a panic while
+/// hashing it.
+pub fn hash_files_with_options<I, P>(hash_algorithm: HashAlgorithm, paths: I, options: &BatchOptions) -> BatchResults<I::IntoIter>
+    where I: IntoIterator<Item=P>, P: AsRef<Path> {
+    hash_files_with(paths, options, move |path, hash_options| hash_file_with_options(hash_algorithm, path, hash_options))
+}
+
+/// Runs `hash` on every path on a pool of worker threads. A panic in `hash` is returned as an
+/// `io::Error` for that path, so every path still yields exactly one result.
+pub(crate) fn hash_files_with<I, P, F>(paths: I, options: &BatchOptions, hash: F) -> BatchResults<I::IntoIter>
+    where I: IntoIterator<Item=P>, P: AsRef<Path>,
+          F: Fn(&Path, &HashOptions) -> io::Result<Digest> + Send + Sync + 'static {
+    let hash = Arc::new(hash);
+    let max_in_flight = options.threads * 2;
+    let (job_sender, job_receiver) = mpsc::sync_channel::<Job>(max_in_flight);
+    let (result_sender, result_receiver) = mpsc::channel::<JobResult>();
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    let cancel_token = CancelToken::new();
+    let hash_options = HashOptions::new().cancel_token(cancel_token.clone());
+
+    let workers = (0..options.threads)
+        .map(|_| {
+            let jobs = Arc::clone(&job_receiver);
+            let results = result_sender.clone();
+            let hash_options = hash_options.clone();
+            let cancel_token = cancel_token.clone();
+            let hash = Arc::clone(&hash);
+            thread::spawn(move || loop {
+                let job = match jobs.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => return,
+                };
+                let (index, path) = match job {
+                    Ok(job) => job,
+                    Err(_) => return,
+                };
+                if cancel_token.is_cancelled() {
+                    return;
+                }
+                let result = panic::catch_unwind(panic::AssertUnwindSafe(|| hash(&path, &hash_options)))
+                    .unwrap_or_else(|_| Err(io::Error::other("worker panicked")));
+                if results.send((index, path, result)).is_err() {
+                    return;
+                }
+            })
+        })
+        .collect();
+
+    BatchResults {
+        paths: paths.into_iter(),
+        jobs: Some(job_sender),
+        results: result_receiver,
+        workers,
+        cancel_token,
+        ordered: options.ordered,
+        submitted: 0,
+        in_flight: 0,
+        max_in_flight,
+        next_to_yield: 0,
+        pending: BTreeMap::new(),
+    }
+}
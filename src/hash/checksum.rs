@@ -0,0 +1,435 @@
+use std::{fmt, fs, io};
+use std::path::Path;
+
+use adler::Adler32;
+use crc::{Crc, CRC_32_ISCSI, CRC_32_ISO_HDLC, CRC_64_XZ};
+use xxhash_rust::xxh3::Xxh3Default;
+use xxhash_rust::xxh64::Xxh64;
+
+use super::{read_chunk, DEFAULT_BUFFER_SIZE};
+
+static CRC32_IEEE: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+static CRC32_CASTAGNOLI: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
+static CRC64_XZ: Crc<u64> = Crc::<u64>::new(&CRC_64_XZ);
+
+const FNV32_OFFSET: u32 = 0x811c_9dc5;
+const FNV32_PRIME: u32 = 0x0100_0193;
+const FNV64_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV64_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// This represents the type of non-cryptographic hash or checksum.
+///
+/// These are much faster than any `HashAlgorithm`, and are meant for hash tables, cache keys,
+/// deduplication pre-filters and detecting accidental corruption. They give **no security
+/// guarantees**: collisions and preimages can be crafted on purpose, so never use them to
+/// check that data was not tampered with. This is why they return a `Checksum` instead of a
+/// `Digest`, and cannot be used where a `HashAlgorithm` is expected.
+///
+/// Ideally you should only use this to activate the type of checksum required in this
+/// library, since matching on this type might cause your code to break if new checksum types
+/// are included in newer versions of this library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+    /// XXH3, 64 bit output.
+    XXH3_64,
+    /// XXH3, 128 bit output.
+    XXH3_128,
+    /// XXH64.
+    XXH64,
+    /// CRC-32 with the IEEE polynomial, as used by zlib, gzip, zip and Ethernet.
+    CRC32,
+    /// CRC-32C with the Castagnoli polynomial, as used by iSCSI, ext4 and SCTP.
+    CRC32C,
+    /// CRC-64/XZ, as used by xz.
+    CRC64,
+    /// Adler-32, as used by zlib.
+    ADLER32,
+    /// FNV-1a, 32 bit output.
+    FNV1A32,
+    /// FNV-1a, 64 bit output.
+    FNV1A64,
+    /// MurmurHash3 x86_32, with seed 0.
+    MURMUR3_32,
+    /// MurmurHash3 x64_128, with seed 0.
+    MURMUR3_128,
+}
+
+impl ChecksumAlgorithm {
+    /// The length of the checksum in bytes.
+    pub(crate) fn output_len(self) -> usize {
+        match self {
+            ChecksumAlgorithm::CRC32 | ChecksumAlgorithm::CRC32C | ChecksumAlgorithm::ADLER32 => 4,
+            ChecksumAlgorithm::FNV1A32 | ChecksumAlgorithm::MURMUR3_32 => 4,
+            ChecksumAlgorithm::XXH3_64 | ChecksumAlgorithm::XXH64 => 8,
+            ChecksumAlgorithm::CRC64 | ChecksumAlgorithm::FNV1A64 => 8,
+            ChecksumAlgorithm::XXH3_128 | ChecksumAlgorithm::MURMUR3_128 => 16,
+        }
+    }
+}
+
+/// The result of a non-cryptographic hash or checksum.
+///
+/// The value is an integer, as these algorithms define it. It is rendered as hex the usual
+/// way, most significant byte first and zero padded to the width of the algorithm, so a
+/// CRC-32 prints as 8 hex digits.
+///
+/// For MurmurHash3 x64_128 the two 64 bit halves `h1` and `h2` are combined as
+/// `h1 << 64 | h2`, so the hex is `h1` followed by `h2` like the reference implementation
+/// prints it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Checksum {
+    algorithm: ChecksumAlgorithm,
+    value: u128,
+}
+
+impl Checksum {
+    /// The algorithm that produced this checksum.
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    /// The checksum as an integer.
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    /// The checksum as big endian bytes, as many as the width of the algorithm.
+    pub fn to_be_bytes(&self) -> Vec<u8> {
+        self.value.to_be_bytes()[16 - self.algorithm.output_len()..].to_vec()
+    }
+
+    /// Encodes the checksum as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_be_bytes())
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::LowerHex for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// An incremental MurmurHash3, which only consumes whole blocks.
+#[derive(Clone)]
+struct Murmur3<S> {
+    state: S,
+    tail: [u8; 16],
+    tail_len: usize,
+    len: u64,
+}
+
+trait MurmurState: Clone {
+    const BLOCK_LEN: usize;
+    fn block(&mut self, block: &[u8]);
+    fn finish(&self, tail: &[u8], len: u64) -> u128;
+}
+
+impl<S: MurmurState> Murmur3<S> {
+    fn new(state: S) -> Self {
+        Murmur3 { state, tail: [0; 16], tail_len: 0, len: 0 }
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        self.len += input.len() as u64;
+        if self.tail_len > 0 {
+            let take = (S::BLOCK_LEN - self.tail_len).min(input.len());
+            self.tail[self.tail_len..self.tail_len + take].copy_from_slice(&input[..take]);
+            self.tail_len += take;
+            input = &input[take..];
+            if self.tail_len < S::BLOCK_LEN {
+                return;
+            }
+            let block = self.tail;
+            self.state.block(&block[..S::BLOCK_LEN]);
+            self.tail_len = 0;
+        }
+        let mut blocks = input.chunks_exact(S::BLOCK_LEN);
+        for block in &mut blocks {
+            self.state.block(block);
+        }
+        let rest = blocks.remainder();
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    fn finish(&self) -> u128 {
+        self.state.finish(&self.tail[..self.tail_len], self.len)
+    }
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    let mut padded = [0; 8];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(padded)
+}
+
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+#[derive(Clone, Default)]
+struct Murmur3x86_32 {
+    h: u32,
+}
+
+impl Murmur3x86_32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    fn mix(k: u32) -> u32 {
+        k.wrapping_mul(Self::C1).rotate_left(15).wrapping_mul(Self::C2)
+    }
+}
+
+impl MurmurState for Murmur3x86_32 {
+    const BLOCK_LEN: usize = 4;
+
+    fn block(&mut self, block: &[u8]) {
+        self.h ^= Self::mix(le_u64(block) as u32);
+        self.h = self.h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    fn finish(&self, tail: &[u8], len: u64) -> u128 {
+        let mut h = self.h;
+        if !tail.is_empty() {
+            h ^= Self::mix(le_u64(tail) as u32);
+        }
+        // The length is mixed in modulo 2^32, as in the reference implementation.
+        h ^= len as u32;
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x85eb_ca6b);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0xc2b2_ae35);
+        u128::from(h ^ (h >> 16))
+    }
+}
+
+#[derive(Clone, Default)]
+struct Murmur3x64_128 {
+    h1: u64,
+    h2: u64,
+}
+
+impl Murmur3x64_128 {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+    fn mix1(k: u64) -> u64 {
+        k.wrapping_mul(Self::C1).rotate_left(31).wrapping_mul(Self::C2)
+    }
+
+    fn mix2(k: u64) -> u64 {
+        k.wrapping_mul(Self::C2).rotate_left(33).wrapping_mul(Self::C1)
+    }
+}
+
+impl MurmurState for Murmur3x64_128 {
+    const BLOCK_LEN: usize = 16;
+
+    fn block(&mut self, block: &[u8]) {
+        self.h1 ^= Self::mix1(le_u64(&block[..8]));
+        self.h1 = self.h1.rotate_left(27).wrapping_add(self.h2).wrapping_mul(5).wrapping_add(0x52dc_e729);
+        self.h2 ^= Self::mix2(le_u64(&block[8..]));
+        self.h2 = self.h2.rotate_left(31).wrapping_add(self.h1).wrapping_mul(5).wrapping_add(0x3849_5ab5);
+    }
+
+    fn finish(&self, tail: &[u8], len: u64) -> u128 {
+        let (mut h1, mut h2) = (self.h1, self.h2);
+        if tail.len() > 8 {
+            h2 ^= Self::mix2(le_u64(&tail[8..]));
+        }
+        if !tail.is_empty() {
+            h1 ^= Self::mix1(le_u64(&tail[..tail.len().min(8)]));
+        }
+        h1 ^= len;
+        h2 ^= len;
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        h1 = fmix64(h1);
+        h2 = fmix64(h2);
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        (u128::from(h1) << 64) | u128::from(h2)
+    }
+}
+
+#[derive(Clone)]
+enum Inner {
+    XXH3(Box<Xxh3Default>),
+    XXH64(Xxh64),
+    CRC32(crc::Digest<'static, u32>),
+    CRC64(crc::Digest<'static, u64>),
+    ADLER32(Adler32),
+    FNV1A32(u32),
+    FNV1A64(u64),
+    MURMUR3_32(Murmur3<Murmur3x86_32>),
+    MURMUR3_128(Murmur3<Murmur3x64_128>),
+}
+
+/// An incremental hasher for any of the `ChecksumAlgorithm` variants.
+///
+/// This works like `Hasher`, but returns a `Checksum`. See `ChecksumAlgorithm` for why these
+/// must not be used where security matters.
+///
+/// ```
+/// use estahr::hash::{ChecksumAlgorithm, Checksummer};
+///
+/// let mut checksummer = Checksummer::new(ChecksumAlgorithm::CRC32);
+/// checksummer.update(b"12345");
+/// checksummer.update(b"6789");
+/// assert_eq!(checksummer.finalize().value(), 0xcbf43926);
+/// ```
+#[derive(Clone)]
+pub struct Checksummer {
+    algorithm: ChecksumAlgorithm,
+    inner: Inner,
+}
+
+impl Checksummer {
+    /// Creates a new checksummer for the selected algorithm.
+    pub fn new(checksum_algorithm: ChecksumAlgorithm) -> Checksummer {
+        let inner = match checksum_algorithm {
+            ChecksumAlgorithm::XXH3_64 | ChecksumAlgorithm::XXH3_128 => Inner::XXH3(Box::new(Xxh3Default::new())),
+            ChecksumAlgorithm::XXH64 => Inner::XXH64(Xxh64::new(0)),
+            ChecksumAlgorithm::CRC32 => Inner::CRC32(CRC32_IEEE.digest()),
+            ChecksumAlgorithm::CRC32C => Inner::CRC32(CRC32_CASTAGNOLI.digest()),
+            ChecksumAlgorithm::CRC64 => Inner::CRC64(CRC64_XZ.digest()),
+            ChecksumAlgorithm::ADLER32 => Inner::ADLER32(Adler32::new()),
+            ChecksumAlgorithm::FNV1A32 => Inner::FNV1A32(FNV32_OFFSET),
+            ChecksumAlgorithm::FNV1A64 => Inner::FNV1A64(FNV64_OFFSET),
+            ChecksumAlgorithm::MURMUR3_32 => Inner::MURMUR3_32(Murmur3::new(Murmur3x86_32::default())),
+            ChecksumAlgorithm::MURMUR3_128 => Inner::MURMUR3_128(Murmur3::new(Murmur3x64_128::default())),
+        };
+        Checksummer { algorithm: checksum_algorithm, inner }
+    }
+
+    /// The algorithm used by this checksummer.
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    /// Feeds more data into the checksummer.
+    pub fn update(&mut self, input: &[u8]) {
+        match &mut self.inner {
+            Inner::XXH3(hasher) => hasher.update(input),
+            Inner::XXH64(hasher) => hasher.update(input),
+            Inner::CRC32(digest) => digest.update(input),
+            Inner::CRC64(digest) => digest.update(input),
+            Inner::ADLER32(adler) => adler.write_slice(input),
+            Inner::FNV1A32(hash) => {
+                for &byte in input {
+                    *hash = (*hash ^ u32::from(byte)).wrapping_mul(FNV32_PRIME);
+                }
+            }
+            Inner::FNV1A64(hash) => {
+                for &byte in input {
+                    *hash = (*hash ^ u64::from(byte)).wrapping_mul(FNV64_PRIME);
+                }
+            }
+            Inner::MURMUR3_32(murmur) => murmur.update(input),
+            Inner::MURMUR3_128(murmur) => murmur.update(input),
+        }
+    }
+
+    /// Returns the checksum of all the data fed so far.
+    ///
+    /// The checksummer is left untouched, so more data can still be fed after this.
+    pub fn finalize(&self) -> Checksum {
+        let value = match &self.inner {
+            Inner::XXH3(hasher) => match self.algorithm {
+                ChecksumAlgorithm::XXH3_128 => hasher.digest128(),
+                _ => u128::from(hasher.digest()),
+            },
+            Inner::XXH64(hasher) => u128::from(hasher.digest()),
+            Inner::CRC32(digest) => u128::from(digest.clone().finalize()),
+            Inner::CRC64(digest) => u128::from(digest.clone().finalize()),
+            Inner::ADLER32(adler) => u128::from(adler.checksum()),
+            Inner::FNV1A32(hash) => u128::from(*hash),
+            Inner::FNV1A64(hash) => u128::from(*hash),
+            Inner::MURMUR3_32(murmur) => murmur.finish(),
+            Inner::MURMUR3_128(murmur) => murmur.finish(),
+        };
+        Checksum { algorithm: self.algorithm, value }
+    }
+
+    /// Resets the checksummer to its initial state, discarding all the data fed so far.
+    pub fn reset(&mut self) {
+        *self = Checksummer::new(self.algorithm);
+    }
+}
+
+impl fmt::Debug for Checksummer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Checksummer").field("algorithm", &self.algorithm).finish()
+    }
+}
+
+impl io::Write for Checksummer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Computes the checksum of the given bytes with the selected algorithm.
+pub fn checksum_bytes(checksum_algorithm: ChecksumAlgorithm, input: &[u8]) -> Checksum {
+    let mut checksummer = Checksummer::new(checksum_algorithm);
+    checksummer.update(input);
+    checksummer.finalize()
+}
+
+/// Computes the checksum of the given string with the selected algorithm.
+pub fn checksum_string(checksum_algorithm: ChecksumAlgorithm, input: &str) -> Checksum {
+    checksum_bytes(checksum_algorithm, input.as_bytes())
+}
+
+fn checksum_stream(checksum_algorithm: ChecksumAlgorithm, stream: &mut dyn io::Read) -> io::Result<Checksum> {
+    let mut checksummer = Checksummer::new(checksum_algorithm);
+    let mut buffer = vec![0; DEFAULT_BUFFER_SIZE];
+    loop {
+        let read = read_chunk(stream, &mut buffer)?;
+        if read == 0 {
+            return Ok(checksummer.finalize());
+        }
+        checksummer.update(&buffer[..read]);
+    }
+}
+
+/// Computes the checksum of everything that can be read from the given reader.
+///
+/// The reader is read until EOF. Returns an `io::Error` if any read fails.
+pub fn checksum_reader<R: io::Read>(checksum_algorithm: ChecksumAlgorithm, mut reader: R) -> io::Result<Checksum> {
+    checksum_stream(checksum_algorithm, &mut reader)
+}
+
+/// Computes the checksum of the standard input of the process with the selected algorithm.
+///
+/// The standard input is read until EOF.
+pub fn checksum_stdin(checksum_algorithm: ChecksumAlgorithm) -> io::Result<Checksum> {
+    let stdin = io::stdin();
+    let mut handle = stdin.lock();
+    checksum_stream(checksum_algorithm, &mut handle)
+}
+
+/// Computes the checksum of the given file with the selected algorithm.
+///
+/// Returns an `io::Error` if the file cannot be opened for any reason.
+pub fn checksum_file<P: AsRef<Path>>(checksum_algorithm: ChecksumAlgorithm, path: P) -> io::Result<Checksum> {
+    let mut file = fs::File::open(path)?;
+    checksum_stream(checksum_algorithm, &mut file)
+}
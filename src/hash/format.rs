@@ -0,0 +1,156 @@
+use std::io;
+use std::path::Path;
+
+use super::{hash_file, Digest, DigestParseError, HashAlgorithm, UnsupportedAlgorithm};
+
+/// The hash algorithms allowed in Subresource Integrity strings, weakest first.
+const SRI_ALGORITHMS: [(HashAlgorithm, &str); 3] = [
+    (HashAlgorithm::SHA2_256, "sha256"),
+    (HashAlgorithm::SHA2_384, "sha384"),
+    (HashAlgorithm::SHA2_512, "sha512"),
+];
+
+fn sri_name(algorithm: HashAlgorithm) -> Option<&'static str> {
+    SRI_ALGORITHMS.iter().find(|(candidate, _)| *candidate == algorithm).map(|(_, name)| *name)
+}
+
+/// The rank of an algorithm when several digests are given, weakest first.
+///
+/// The broken algorithms (`is_broken`) rank below all the others, then longer outputs rank
+/// higher, and algorithms with the same output length are ordered as in `HashAlgorithm::all`.
+/// Every algorithm has its own rank, so the strongest one does not depend on the order of
+/// the digests, and the SRI algorithms keep the order of the specification.
+fn strength(algorithm: HashAlgorithm) -> (bool, usize, usize) {
+    let position = HashAlgorithm::all().iter().position(|&candidate| candidate == algorithm).unwrap_or(0);
+    (!algorithm.is_broken(), algorithm.output_len(), position)
+}
+
+/// Appends `value` as an unsigned LEB128 varint, as multiformats does.
+fn write_varint(mut value: u64, output: &mut Vec<u8>) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+/// Reads an unsigned LEB128 varint, returning it and the rest of the input.
+fn read_varint(input: &[u8]) -> Result<(u64, &[u8]), DigestParseError> {
+    let mut value = 0u64;
+    for (i, &byte) in input.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, &input[i + 1..]));
+        }
+    }
+    Err(DigestParseError::InvalidEncoding("truncated or overlong varint".to_string()))
+}
+
+impl Digest {
+    /// Encodes the digest as a binary multihash: the multicodec code of the algorithm and the
+    /// digest length, both as varints, followed by the digest bytes.
+    ///
+    /// Returns an error for algorithms without a multicodec code (Whirlpool).
+    pub fn to_multihash(&self) -> Result<Vec<u8>, UnsupportedAlgorithm> {
+        let code = self.algorithm().multicodec().ok_or(UnsupportedAlgorithm(self.algorithm()))?;
+        let mut output = Vec::with_capacity(self.len() + 4);
+        write_varint(code, &mut output);
+        write_varint(self.len() as u64, &mut output);
+        output.extend_from_slice(self.as_bytes());
+        Ok(output)
+    }
+
+    /// Parses a binary multihash.
+    ///
+    /// Truncated digests are rejected: the length must be the full output length of the
+    /// algorithm.
+    pub fn from_multihash(input: &[u8]) -> Result<Digest, DigestParseError> {
+        let (code, rest) = read_varint(input)?;
+        let algorithm = HashAlgorithm::from_multicodec(code)
+            .ok_or_else(|| DigestParseError::UnknownAlgorithm(format!("multicodec 0x{:x}", code)))?;
+        let (len, bytes) = read_varint(rest)?;
+        if len != bytes.len() as u64 {
+            return Err(DigestParseError::InvalidEncoding(
+                format!("multihash declares {} bytes but holds {}", len, bytes.len())));
+        }
+        Digest::from_bytes(algorithm, bytes)
+    }
+
+    /// Encodes the digest as a W3C Subresource Integrity string, such as `sha384-<base64>`.
+    ///
+    /// Subresource Integrity only allows SHA-256, SHA-384 and SHA-512, other algorithms
+    /// return an error.
+    pub fn to_sri(&self) -> Result<String, UnsupportedAlgorithm> {
+        let name = sri_name(self.algorithm()).ok_or(UnsupportedAlgorithm(self.algorithm()))?;
+        Ok(format!("{}-{}", name, self.to_base64()))
+    }
+
+    /// Parses a single Subresource Integrity string, such as `sha384-<base64>`.
+    ///
+    /// Options after a `?` are ignored. For a whole `integrity` attribute, which may list
+    /// several digests, see `verify_file`.
+    pub fn from_sri(input: &str) -> Result<Digest, DigestParseError> {
+        let input = input.split('?').next().unwrap_or_default();
+        let (name, value) = input.split_once('-')
+            .ok_or_else(|| DigestParseError::InvalidEncoding("missing '-' after the algorithm".to_string()))?;
+        let algorithm = SRI_ALGORITHMS.iter()
+            .find(|(_, candidate)| *candidate == name)
+            .map(|(algorithm, _)| *algorithm)
+            .ok_or_else(|| DigestParseError::UnknownAlgorithm(name.to_string()))?;
+        Digest::from_base64(algorithm, value)
+    }
+
+    /// Encodes the digest as its algorithm name and hex value separated by a colon, such as
+    /// `sha2-256:<hex>`. The name is the one given by `HashAlgorithm::name`.
+    pub fn to_prefixed_hex(&self) -> String {
+        format!("{}:{}", self.algorithm(), self.to_hex())
+    }
+
+    /// Parses an algorithm name and hex value separated by a colon, such as `sha256:<hex>`.
+    ///
+    /// Any spelling accepted by `HashAlgorithm::from_str` can be used for the name.
+    pub fn from_prefixed_hex(input: &str) -> Result<Digest, DigestParseError> {
+        let (name, value) = input.split_once(':')
+            .ok_or_else(|| DigestParseError::InvalidEncoding("missing ':' after the algorithm".to_string()))?;
+        let algorithm = name.parse::<HashAlgorithm>()
+            .map_err(|_| DigestParseError::UnknownAlgorithm(name.to_string()))?;
+        Digest::from_hex(algorithm, value)
+    }
+
+    /// Parses any of the self-describing forms: `algo:hex`, a Subresource Integrity string,
+    /// or a hex encoded multihash.
+    pub fn parse(input: &str) -> Result<Digest, DigestParseError> {
+        let input = input.trim();
+        if input.contains(':') {
+            Digest::from_prefixed_hex(input)
+        } else if input.contains('-') {
+            Digest::from_sri(input)
+        } else {
+            let bytes = hex::decode(input).map_err(|e| DigestParseError::InvalidEncoding(e.to_string()))?;
+            Digest::from_multihash(&bytes)
+        }
+    }
+}
+
+/// Checks whether the given file matches a self-describing digest string.
+///
+/// The string can be in any form accepted by `Digest::parse`. It can also be a whole
+/// Subresource Integrity attribute with several whitespace separated digests: as the
+/// specification requires, only the digests with the strongest algorithm are considered,
+/// and the file matches if it matches any of them. Digests in other forms can be mixed in;
+/// the broken algorithms (MD5 and SHA-1) are then only used if nothing stronger is given.
+///
+/// Returns an `io::Error` of kind `InvalidInput` if the string cannot be parsed, or any
+/// error met while reading the file.
+pub fn verify_file<P: AsRef<Path>>(path: P, expected: &str) -> io::Result<bool> {
+    let digests = expected.split_whitespace()
+        .map(Digest::parse)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let strongest = digests.iter()
+        .map(|digest| digest.algorithm())
+        .max_by_key(|&algorithm| strength(algorithm))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no digest to verify against"))?;
+    let actual = hash_file(strongest, path)?;
+    Ok(digests.iter().filter(|digest| digest.algorithm() == strongest).any(|digest| digest.ct_eq(&actual)))
+}
@@ -0,0 +1,157 @@
+use std::{fmt, io};
+
+use blake2::{Blake2b, Blake2s};
+use digest::Digest as _;
+use md5::Md5;
+use ripemd160::Ripemd160;
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512Trunc256};
+use sha3::{Keccak256, Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+use whirlpool::Whirlpool;
+
+use super::{Digest, HashAlgorithm};
+
+#[derive(Clone)]
+enum Inner {
+    MD5(Md5),
+    SHA2_256(Sha256),
+    SHA2_512(Sha512),
+    SHA3_256(Sha3_256),
+    SHA3_512(Sha3_512),
+    BLAKE2S(Blake2s),
+    BLAKE2B(Blake2b),
+    BLAKE3(Box<blake3::Hasher>),
+    SHA1(Sha1),
+    SHA2_224(Sha224),
+    SHA2_384(Sha384),
+    SHA2_512_256(Sha512Trunc256),
+    SHA3_224(Sha3_224),
+    SHA3_384(Sha3_384),
+    KECCAK256(Keccak256),
+    RIPEMD160(Ripemd160),
+    #[allow(clippy::upper_case_acronyms)]
+    WHIRLPOOL(Whirlpool),
+}
+
+/// An incremental hasher for any of the `HashAlgorithm` variants.
+///
+/// Data can be fed in pieces with `update` (or through `io::Write`), and the digest of
+/// everything fed so far is returned by `finalize`.
+///
+/// ```
+/// use estahr::hash::{hash_string, HashAlgorithm, Hasher};
+///
+/// let mut hasher = Hasher::new(HashAlgorithm::SHA2_256);
+/// hasher.update(b"hello ");
+/// hasher.update(b"world");
+/// assert_eq!(hasher.finalize(), hash_string(HashAlgorithm::SHA2_256, "hello world"));
+/// ```
+#[derive(Clone)]
+pub struct Hasher {
+    algorithm: HashAlgorithm,
+    inner: Inner,
+}
+
+impl Hasher {
+    /// Creates a new hasher for the selected hash algorithm.
+    pub fn new(hash_algorithm: HashAlgorithm) -> Hasher {
+        let inner = match hash_algorithm {
+            HashAlgorithm::MD5 => Inner::MD5(Md5::new()),
+            HashAlgorithm::SHA2_256 => Inner::SHA2_256(Sha256::new()),
+            HashAlgorithm::SHA2_512 => Inner::SHA2_512(Sha512::new()),
+            HashAlgorithm::SHA3_256 => Inner::SHA3_256(Sha3_256::new()),
+            HashAlgorithm::SHA3_512 => Inner::SHA3_512(Sha3_512::new()),
+            HashAlgorithm::BLAKE2S => Inner::BLAKE2S(Blake2s::new()),
+            HashAlgorithm::BLAKE2B => Inner::BLAKE2B(Blake2b::new()),
+            HashAlgorithm::BLAKE3 => Inner::BLAKE3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::SHA1 => Inner::SHA1(Sha1::new()),
+            HashAlgorithm::SHA2_224 => Inner::SHA2_224(Sha224::new()),
+            HashAlgorithm::SHA2_384 => Inner::SHA2_384(Sha384::new()),
+            HashAlgorithm::SHA2_512_256 => Inner::SHA2_512_256(Sha512Trunc256::new()),
+            HashAlgorithm::SHA3_224 => Inner::SHA3_224(Sha3_224::new()),
+            HashAlgorithm::SHA3_384 => Inner::SHA3_384(Sha3_384::new()),
+            HashAlgorithm::KECCAK256 => Inner::KECCAK256(Keccak256::new()),
+            HashAlgorithm::RIPEMD160 => Inner::RIPEMD160(Ripemd160::new()),
+            HashAlgorithm::WHIRLPOOL => Inner::WHIRLPOOL(Whirlpool::new()),
+        };
+        Hasher { algorithm: hash_algorithm, inner }
+    }
+
+    /// The hash algorithm used by this hasher.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Feeds more data into the hasher.
+    pub fn update(&mut self, input: &[u8]) {
+        match &mut self.inner {
+            Inner::MD5(hasher) => hasher.input(input),
+            Inner::SHA2_256(hasher) => hasher.input(input),
+            Inner::SHA2_512(hasher) => hasher.input(input),
+            Inner::SHA3_256(hasher) => hasher.input(input),
+            Inner::SHA3_512(hasher) => hasher.input(input),
+            Inner::BLAKE2S(hasher) => hasher.input(input),
+            Inner::BLAKE2B(hasher) => hasher.input(input),
+            Inner::BLAKE3(hasher) => {
+                hasher.update(input);
+            }
+            Inner::SHA1(hasher) => hasher.input(input),
+            Inner::SHA2_224(hasher) => hasher.input(input),
+            Inner::SHA2_384(hasher) => hasher.input(input),
+            Inner::SHA2_512_256(hasher) => hasher.input(input),
+            Inner::SHA3_224(hasher) => hasher.input(input),
+            Inner::SHA3_384(hasher) => hasher.input(input),
+            Inner::KECCAK256(hasher) => hasher.input(input),
+            Inner::RIPEMD160(hasher) => hasher.input(input),
+            Inner::WHIRLPOOL(hasher) => hasher.input(input),
+        }
+    }
+
+    /// Returns the digest of all the data fed so far.
+    ///
+    /// The hasher is left untouched, so more data can still be fed after this.
+    pub fn finalize(&self) -> Digest {
+        let bytes = match &self.inner {
+            Inner::MD5(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA2_256(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA2_512(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA3_256(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA3_512(hasher) => hasher.clone().result().to_vec(),
+            Inner::BLAKE2S(hasher) => hasher.clone().result().to_vec(),
+            Inner::BLAKE2B(hasher) => hasher.clone().result().to_vec(),
+            Inner::BLAKE3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            Inner::SHA1(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA2_224(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA2_384(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA2_512_256(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA3_224(hasher) => hasher.clone().result().to_vec(),
+            Inner::SHA3_384(hasher) => hasher.clone().result().to_vec(),
+            Inner::KECCAK256(hasher) => hasher.clone().result().to_vec(),
+            Inner::RIPEMD160(hasher) => hasher.clone().result().to_vec(),
+            Inner::WHIRLPOOL(hasher) => hasher.clone().result().to_vec(),
+        };
+        Digest::new(self.algorithm, bytes)
+    }
+
+    /// Resets the hasher to its initial state, discarding all the data fed so far.
+    pub fn reset(&mut self) {
+        *self = Hasher::new(self.algorithm);
+    }
+}
+
+impl fmt::Debug for Hasher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hasher").field("algorithm", &self.algorithm).finish()
+    }
+}
+
+impl io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+use std::{error, fmt, io};
+
+use super::{Digest, HashAlgorithm};
+
+const BLAKE2B_KEY_LEN: usize = 64;
+const BLAKE2B_SALT_LEN: usize = 16;
+const BLAKE2S_KEY_LEN: usize = 32;
+const BLAKE2S_SALT_LEN: usize = 8;
+const BLAKE3_KEY_LEN: usize = 32;
+
+/// The error returned when a keyed hasher cannot be created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyedHashError {
+    /// The hash algorithm has no keyed mode. Only BLAKE2s, BLAKE2b and BLAKE3 have one.
+    UnsupportedAlgorithm(HashAlgorithm),
+    /// The key is longer than the algorithm allows (BLAKE2).
+    InvalidKeyLength { max: usize, actual: usize },
+    /// The key does not have the exact length the algorithm needs (32 bytes for BLAKE3).
+    WrongKeyLength { expected: usize, actual: usize },
+    /// The salt is longer than the algorithm allows.
+    InvalidSaltLength { max: usize, actual: usize },
+    /// The personalization is longer than the algorithm allows.
+    InvalidPersonalLength { max: usize, actual: usize },
+}
+
+impl fmt::Display for KeyedHashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyedHashError::UnsupportedAlgorithm(algorithm) => write!(f, "{:?} has no keyed mode", algorithm),
+            KeyedHashError::InvalidKeyLength { max, actual } => write!(f, "invalid key length {} (max {})", actual, max),
+            KeyedHashError::WrongKeyLength { expected, actual } => {
+                write!(f, "invalid key length {} (expected {})", actual, expected)
+            }
+            KeyedHashError::InvalidSaltLength { max, actual } => write!(f, "invalid salt length {} (max {})", actual, max),
+            KeyedHashError::InvalidPersonalLength { max, actual } => {
+                write!(f, "invalid personalization length {} (max {})", actual, max)
+            }
+        }
+    }
+}
+
+impl error::Error for KeyedHashError {}
+
+/// The parameters of a BLAKE2 hash: key, salt and personalization.
+///
+/// All of them are optional. BLAKE2b accepts keys of up to 64 bytes and salts and
+/// personalizations of up to 16 bytes. BLAKE2s accepts keys of up to 32 bytes and salts and
+/// personalizations of up to 8 bytes. Shorter salts and personalizations are padded with
+/// zeros.
+///
+/// ```
+/// use estahr::hash::{Blake2Params, HashAlgorithm, KeyedHasher};
+///
+/// let params = Blake2Params::new().key(b"secret").salt(b"tenant-42").personal(b"tokens");
+/// let mut hasher = KeyedHasher::with_blake2_params(HashAlgorithm::BLAKE2B, &params).unwrap();
+/// hasher.update(b"payload");
+/// let tag = hasher.finalize();
+/// ```
+#[derive(Clone, Default)]
+pub struct Blake2Params {
+    key: Vec<u8>,
+    salt: Vec<u8>,
+    personal: Vec<u8>,
+}
+
+impl Blake2Params {
+    /// Empty parameters: no key, no salt and no personalization.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the key.
+    pub fn key(mut self, key: &[u8]) -> Self {
+        self.key = key.to_vec();
+        self
+    }
+
+    /// Sets the salt.
+    pub fn salt(mut self, salt: &[u8]) -> Self {
+        self.salt = salt.to_vec();
+        self
+    }
+
+    /// Sets the personalization.
+    pub fn personal(mut self, personal: &[u8]) -> Self {
+        self.personal = personal.to_vec();
+        self
+    }
+
+    fn check(&self, key_len: usize, salt_len: usize) -> Result<(), KeyedHashError> {
+        if self.key.len() > key_len {
+            return Err(KeyedHashError::InvalidKeyLength { max: key_len, actual: self.key.len() });
+        }
+        if self.salt.len() > salt_len {
+            return Err(KeyedHashError::InvalidSaltLength { max: salt_len, actual: self.salt.len() });
+        }
+        if self.personal.len() > salt_len {
+            return Err(KeyedHashError::InvalidPersonalLength { max: salt_len, actual: self.personal.len() });
+        }
+        Ok(())
+    }
+}
+
+// Keys must not show up in logs.
+impl fmt::Debug for Blake2Params {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Blake2Params")
+            .field("key", &format_args!("[{} bytes]", self.key.len()))
+            .field("salt", &self.salt)
+            .field("personal", &self.personal)
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+enum Inner {
+    BLAKE2S(blake2s_simd::State),
+    BLAKE2B(blake2b_simd::State),
+    BLAKE3(Box<blake3::Hasher>),
+}
+
+/// An incremental keyed hasher for BLAKE2s, BLAKE2b and BLAKE3.
+///
+/// These algorithms are MACs on their own when given a key, so they do not need HMAC.
+/// BLAKE3 also has a key derivation mode, see `KeyedHasher::new_derive_key`.
+#[derive(Clone)]
+pub struct KeyedHasher {
+    algorithm: HashAlgorithm,
+    inner: Inner,
+    initial: Inner,
+}
+
+impl KeyedHasher {
+    fn from_inner(algorithm: HashAlgorithm, inner: Inner) -> KeyedHasher {
+        KeyedHasher { algorithm, initial: inner.clone(), inner }
+    }
+
+    /// Creates a keyed hasher for the selected hash algorithm.
+    ///
+    /// BLAKE3 needs a key of exactly 32 bytes. BLAKE2b accepts keys of up to 64 bytes and
+    /// BLAKE2s keys of up to 32 bytes.
+    pub fn new(hash_algorithm: HashAlgorithm, key: &[u8]) -> Result<KeyedHasher, KeyedHashError> {
+        match hash_algorithm {
+            HashAlgorithm::BLAKE3 => {
+                if key.len() != BLAKE3_KEY_LEN {
+                    return Err(KeyedHashError::WrongKeyLength { expected: BLAKE3_KEY_LEN, actual: key.len() });
+                }
+                let mut blake3_key = [0; BLAKE3_KEY_LEN];
+                blake3_key.copy_from_slice(key);
+                let hasher = blake3::Hasher::new_keyed(&blake3_key);
+                Ok(KeyedHasher::from_inner(hash_algorithm, Inner::BLAKE3(Box::new(hasher))))
+            }
+            _ => KeyedHasher::with_blake2_params(hash_algorithm, &Blake2Params::new().key(key)),
+        }
+    }
+
+    /// Creates a BLAKE2s or BLAKE2b hasher with the given key, salt and personalization.
+    pub fn with_blake2_params(hash_algorithm: HashAlgorithm, params: &Blake2Params) -> Result<KeyedHasher, KeyedHashError> {
+        let inner = match hash_algorithm {
+            HashAlgorithm::BLAKE2B => {
+                params.check(BLAKE2B_KEY_LEN, BLAKE2B_SALT_LEN)?;
+                let state = blake2b_simd::Params::new()
+                    .key(&params.key)
+                    .salt(&params.salt)
+                    .personal(&params.personal)
+                    .to_state();
+                Inner::BLAKE2B(state)
+            }
+            HashAlgorithm::BLAKE2S => {
+                params.check(BLAKE2S_KEY_LEN, BLAKE2S_SALT_LEN)?;
+                let state = blake2s_simd::Params::new()
+                    .key(&params.key)
+                    .salt(&params.salt)
+                    .personal(&params.personal)
+                    .to_state();
+                Inner::BLAKE2S(state)
+            }
+            _ => return Err(KeyedHashError::UnsupportedAlgorithm(hash_algorithm)),
+        };
+        Ok(KeyedHasher::from_inner(hash_algorithm, inner))
+    }
+
+    /// Creates a BLAKE3 hasher in key derivation mode.
+    ///
+    /// The context string should be hardcoded, globally unique and application specific. The
+    /// key material is then fed with `update`, and `finalize` returns the derived key.
+    pub fn new_derive_key(context: &str) -> KeyedHasher {
+        let hasher = blake3::Hasher::new_derive_key(context);
+        KeyedHasher::from_inner(HashAlgorithm::BLAKE3, Inner::BLAKE3(Box::new(hasher)))
+    }
+
+    /// The hash algorithm used by this hasher.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Feeds more data into the hasher.
+    pub fn update(&mut self, input: &[u8]) {
+        match &mut self.inner {
+            Inner::BLAKE2S(state) => {
+                state.update(input);
+            }
+            Inner::BLAKE2B(state) => {
+                state.update(input);
+            }
+            Inner::BLAKE3(hasher) => {
+                hasher.update(input);
+            }
+        }
+    }
+
+    /// Returns the keyed digest of all the data fed so far.
+    ///
+    /// The hasher is left untouched, so more data can still be fed after this.
+    pub fn finalize(&self) -> Digest {
+        let bytes = match &self.inner {
+            Inner::BLAKE2S(state) => state.finalize().as_bytes().to_vec(),
+            Inner::BLAKE2B(state) => state.finalize().as_bytes().to_vec(),
+            Inner::BLAKE3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        };
+        Digest::new(self.algorithm, bytes)
+    }
+
+    /// Checks in constant time whether `tag` is the keyed digest of all the data fed so far.
+    pub fn verify(&self, tag: &[u8]) -> bool {
+        constant_time_eq::constant_time_eq(self.finalize().as_bytes(), tag)
+    }
+
+    /// Resets the hasher to its initial state, keeping the key and parameters.
+    pub fn reset(&mut self) {
+        self.inner = self.initial.clone();
+    }
+}
+
+impl fmt::Debug for KeyedHasher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyedHasher").field("algorithm", &self.algorithm).finish()
+    }
+}
+
+impl io::Write for KeyedHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Computes the keyed hash of the given bytes with BLAKE2s, BLAKE2b or BLAKE3.
+pub fn keyed_hash_bytes(hash_algorithm: HashAlgorithm, key: &[u8], input: &[u8]) -> Result<Digest, KeyedHashError> {
+    let mut hasher = KeyedHasher::new(hash_algorithm, key)?;
+    hasher.update(input);
+    Ok(hasher.finalize())
+}
+
+/// Computes the keyed hash of the given string with BLAKE2s, BLAKE2b or BLAKE3.
+pub fn keyed_hash_string(hash_algorithm: HashAlgorithm, key: &[u8], input: &str) -> Result<Digest, KeyedHashError> {
+    keyed_hash_bytes(hash_algorithm, key, input.as_bytes())
+}
+
+/// Checks in constant time whether `tag` is the keyed hash of the given bytes.
+pub fn keyed_verify(hash_algorithm: HashAlgorithm, key: &[u8], input: &[u8], tag: &[u8]) -> Result<bool, KeyedHashError> {
+    let mut hasher = KeyedHasher::new(hash_algorithm, key)?;
+    hasher.update(input);
+    Ok(hasher.verify(tag))
+}
+
+/// Derives a key of any length from the given key material with BLAKE3, filling `output`.
+///
+/// The context string should be hardcoded, globally unique and application specific, for
+/// example `"example.com 2020-01-01 tenant session tokens v1"`. Different contexts give
+/// independent keys for the same key material.
+pub fn derive_key(context: &str, key_material: &[u8], output: &mut [u8]) {
+    blake3::derive_key(context, key_material, output);
+}
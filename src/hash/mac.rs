@@ -0,0 +1,190 @@
+use std::{fmt, io};
+
+use hmac::{Hmac as HmacOf, Mac};
+use md5::Md5;
+use ripemd160::Ripemd160;
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512Trunc256};
+use sha3::{Keccak256, Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+use whirlpool::Whirlpool;
+
+use super::{Digest, HashAlgorithm, UnsupportedAlgorithm};
+
+#[derive(Clone)]
+enum Inner {
+    MD5(HmacOf<Md5>),
+    SHA2_256(HmacOf<Sha256>),
+    SHA2_512(HmacOf<Sha512>),
+    SHA3_256(HmacOf<Sha3_256>),
+    SHA3_512(HmacOf<Sha3_512>),
+    SHA1(HmacOf<Sha1>),
+    SHA2_224(HmacOf<Sha224>),
+    SHA2_384(HmacOf<Sha384>),
+    SHA2_512_256(HmacOf<Sha512Trunc256>),
+    SHA3_224(HmacOf<Sha3_224>),
+    SHA3_384(HmacOf<Sha3_384>),
+    KECCAK256(HmacOf<Keccak256>),
+    RIPEMD160(HmacOf<Ripemd160>),
+    #[allow(clippy::upper_case_acronyms)]
+    WHIRLPOOL(HmacOf<Whirlpool>),
+}
+
+// HMAC accepts keys of any length, so `new_varkey` cannot fail.
+fn keyed<M: Mac>(key: &[u8]) -> M {
+    M::new_varkey(key).expect("HMAC accepts keys of any length")
+}
+
+/// An incremental HMAC (RFC 2104) over one of the `HashAlgorithm` variants.
+///
+/// HMAC is available for MD5, SHA-1, RIPEMD-160, Keccak-256, Whirlpool and the SHA-2 and SHA-3
+/// variants. BLAKE2 and BLAKE3 have their own keyed modes and are rejected with
+/// `UnsupportedAlgorithm`.
+///
+/// The returned `Digest` is tagged with the underlying hash algorithm.
+///
+/// ```
+/// use estahr::hash::{HashAlgorithm, Hmac};
+///
+/// let mut hmac = Hmac::new(HashAlgorithm::SHA2_256, b"key").unwrap();
+/// hmac.update(b"The quick brown fox jumps over the lazy dog");
+/// assert_eq!(hmac.finalize().to_hex(), "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+/// ```
+#[derive(Clone)]
+pub struct Hmac {
+    algorithm: HashAlgorithm,
+    inner: Inner,
+}
+
+impl Hmac {
+    /// Creates a new HMAC with the given key, for the selected hash algorithm.
+    pub fn new(hash_algorithm: HashAlgorithm, key: &[u8]) -> Result<Hmac, UnsupportedAlgorithm> {
+        let inner = match hash_algorithm {
+            HashAlgorithm::MD5 => Inner::MD5(keyed(key)),
+            HashAlgorithm::SHA2_256 => Inner::SHA2_256(keyed(key)),
+            HashAlgorithm::SHA2_512 => Inner::SHA2_512(keyed(key)),
+            HashAlgorithm::SHA3_256 => Inner::SHA3_256(keyed(key)),
+            HashAlgorithm::SHA3_512 => Inner::SHA3_512(keyed(key)),
+            HashAlgorithm::SHA1 => Inner::SHA1(keyed(key)),
+            HashAlgorithm::SHA2_224 => Inner::SHA2_224(keyed(key)),
+            HashAlgorithm::SHA2_384 => Inner::SHA2_384(keyed(key)),
+            HashAlgorithm::SHA2_512_256 => Inner::SHA2_512_256(keyed(key)),
+            HashAlgorithm::SHA3_224 => Inner::SHA3_224(keyed(key)),
+            HashAlgorithm::SHA3_384 => Inner::SHA3_384(keyed(key)),
+            HashAlgorithm::KECCAK256 => Inner::KECCAK256(keyed(key)),
+            HashAlgorithm::RIPEMD160 => Inner::RIPEMD160(keyed(key)),
+            HashAlgorithm::WHIRLPOOL => Inner::WHIRLPOOL(keyed(key)),
+            _ => return Err(UnsupportedAlgorithm(hash_algorithm)),
+        };
+        Ok(Hmac { algorithm: hash_algorithm, inner })
+    }
+
+    /// The hash algorithm used by this HMAC.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Feeds more data into the HMAC.
+    pub fn update(&mut self, input: &[u8]) {
+        match &mut self.inner {
+            Inner::MD5(mac) => mac.input(input),
+            Inner::SHA2_256(mac) => mac.input(input),
+            Inner::SHA2_512(mac) => mac.input(input),
+            Inner::SHA3_256(mac) => mac.input(input),
+            Inner::SHA3_512(mac) => mac.input(input),
+            Inner::SHA1(mac) => mac.input(input),
+            Inner::SHA2_224(mac) => mac.input(input),
+            Inner::SHA2_384(mac) => mac.input(input),
+            Inner::SHA2_512_256(mac) => mac.input(input),
+            Inner::SHA3_224(mac) => mac.input(input),
+            Inner::SHA3_384(mac) => mac.input(input),
+            Inner::KECCAK256(mac) => mac.input(input),
+            Inner::RIPEMD160(mac) => mac.input(input),
+            Inner::WHIRLPOOL(mac) => mac.input(input),
+        }
+    }
+
+    /// Returns the authentication code of all the data fed so far.
+    ///
+    /// The HMAC is left untouched, so more data can still be fed after this.
+    pub fn finalize(&self) -> Digest {
+        let bytes = match &self.inner {
+            Inner::MD5(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA2_256(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA2_512(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA3_256(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA3_512(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA1(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA2_224(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA2_384(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA2_512_256(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA3_224(mac) => mac.clone().result().code().to_vec(),
+            Inner::SHA3_384(mac) => mac.clone().result().code().to_vec(),
+            Inner::KECCAK256(mac) => mac.clone().result().code().to_vec(),
+            Inner::RIPEMD160(mac) => mac.clone().result().code().to_vec(),
+            Inner::WHIRLPOOL(mac) => mac.clone().result().code().to_vec(),
+        };
+        Digest::new(self.algorithm, bytes)
+    }
+
+    /// Checks in constant time whether `tag` is the authentication code of all the data fed
+    /// so far.
+    pub fn verify(&self, tag: &[u8]) -> bool {
+        constant_time_eq::constant_time_eq(self.finalize().as_bytes(), tag)
+    }
+
+    /// Resets the HMAC to its initial state, keeping the key.
+    pub fn reset(&mut self) {
+        match &mut self.inner {
+            Inner::MD5(mac) => mac.reset(),
+            Inner::SHA2_256(mac) => mac.reset(),
+            Inner::SHA2_512(mac) => mac.reset(),
+            Inner::SHA3_256(mac) => mac.reset(),
+            Inner::SHA3_512(mac) => mac.reset(),
+            Inner::SHA1(mac) => mac.reset(),
+            Inner::SHA2_224(mac) => mac.reset(),
+            Inner::SHA2_384(mac) => mac.reset(),
+            Inner::SHA2_512_256(mac) => mac.reset(),
+            Inner::SHA3_224(mac) => mac.reset(),
+            Inner::SHA3_384(mac) => mac.reset(),
+            Inner::KECCAK256(mac) => mac.reset(),
+            Inner::RIPEMD160(mac) => mac.reset(),
+            Inner::WHIRLPOOL(mac) => mac.reset(),
+        }
+    }
+}
+
+impl fmt::Debug for Hmac {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hmac").field("algorithm", &self.algorithm).finish()
+    }
+}
+
+impl io::Write for Hmac {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Computes the HMAC of the given bytes with the given key and hash algorithm.
+pub fn hmac_bytes(hash_algorithm: HashAlgorithm, key: &[u8], input: &[u8]) -> Result<Digest, UnsupportedAlgorithm> {
+    let mut hmac = Hmac::new(hash_algorithm, key)?;
+    hmac.update(input);
+    Ok(hmac.finalize())
+}
+
+/// Computes the HMAC of the given string with the given key and hash algorithm.
+pub fn hmac_string(hash_algorithm: HashAlgorithm, key: &[u8], input: &str) -> Result<Digest, UnsupportedAlgorithm> {
+    hmac_bytes(hash_algorithm, key, input.as_bytes())
+}
+
+/// Checks in constant time whether `tag` is the HMAC of the given bytes.
+pub fn hmac_verify(hash_algorithm: HashAlgorithm, key: &[u8], input: &[u8], tag: &[u8]) -> Result<bool, UnsupportedAlgorithm> {
+    let mut hmac = Hmac::new(hash_algorithm, key)?;
+    hmac.update(input);
+    Ok(hmac.verify(tag))
+}
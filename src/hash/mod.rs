@@ -0,0 +1,390 @@
+//! This module provides functions to calculate the hash of strings and files.
+//!
+//! The hashes are returned as a `Digest`, which holds the raw bytes of the hash and can be
+//! encoded as hex, base64 or base32.
+//!
+//! The hashing (file & string) provided include:
+//! 1. MD5 (for compatibility, not recommended due to proven weaknesses).
+//! 2. SHA2 - 256
+//! 3. SHA2 - 512
+//! 4. SHA3 - 256
+//! 5. SHA3 - 512
+//! 6. BLAKE 2s (256 bit)
+//! 7. BLAKE 2b (512 bit)
+//! 8. BLAKE 3 (256 bit)
+//! 9. SHA1 (for compatibility, not recommended due to proven weaknesses).
+//! 10. SHA2 - 224
+//! 11. SHA2 - 384
+//! 12. SHA2 - 512/256
+//! 13. SHA3 - 224
+//! 14. SHA3 - 384
+//! 15. Keccak - 256 (the original Keccak padding, as used by Ethereum)
+//! 16. RIPEMD - 160
+//! 17. Whirlpool (512 bit)
+//!
+//! Non-cryptographic hashes and checksums (XXH3, XXH64, CRC-32, CRC-32C, CRC-64, Adler-32,
+//! FNV-1a and MurmurHash3) are selected with a separate `ChecksumAlgorithm` and return a
+//! `Checksum`. They are much faster, but give no security guarantees.
+//!
+//! None of these are suitable for storing passwords, as they are fast to brute force: use the
+//! `password` module instead.
+
+extern crate adler;
+extern crate blake2;
+extern crate blake2b_simd;
+extern crate blake2s_simd;
+extern crate blake3;
+extern crate constant_time_eq;
+extern crate crc;
+extern crate data_encoding;
+extern crate digest;
+extern crate glob;
+extern crate hex;
+extern crate hmac;
+extern crate keccak;
+extern crate md5;
+extern crate ripemd160;
+extern crate sha1;
+extern crate sha2;
+extern crate sha3;
+extern crate xxhash_rust;
+
+mod adapters;
+mod batch;
+mod checksum;
+mod format;
+mod hasher;
+mod keyed;
+mod mac;
+mod multi;
+mod options;
+mod output;
+mod partial;
+#[cfg(feature = "tokio")]
+mod tokio_io;
+mod tree;
+mod xof;
+
+use std::{error, fmt, io, str};
+use std::path::Path;
+
+pub use self::adapters::{HashingReader, HashingWriter};
+pub use self::batch::{hash_files, hash_files_with_options, BatchOptions, BatchResults};
+#[cfg(test)]
+pub(crate) use self::batch::hash_files_with;
+pub use self::checksum::{checksum_bytes, checksum_file, checksum_reader, checksum_stdin, checksum_string, Checksum, ChecksumAlgorithm, Checksummer};
+pub use self::format::verify_file;
+pub use self::hasher::Hasher;
+pub use self::keyed::{derive_key, keyed_hash_bytes, keyed_hash_string, keyed_verify, Blake2Params, KeyedHashError, KeyedHasher};
+pub use self::mac::{hmac_bytes, hmac_string, hmac_verify, Hmac};
+pub use self::multi::{hash_file_multi, hash_file_multi_threaded, hash_reader_multi};
+pub use self::options::{hash_file_with_options, hash_file_with_stats, CancelToken, HashOptions, HashReport};
+pub use self::output::{Digest, DigestParseError};
+pub use self::partial::{hash_file_range, hash_file_sampled, SampledDigest};
+#[cfg(feature = "tokio")]
+pub use self::tokio_io::{hash_async_reader, AsyncHashingWriter};
+pub use self::tree::{hash_tree, hash_tree_with_options, TreeOptions};
+pub use self::xof::{xof_bytes, xof_string, Xof, XofAlgorithm, XofReader};
+
+/// This represents the type of hash algorithm.
+///
+/// Ideally you should only use this to activate the type of hash required in this library,
+/// since matching on this type might cause your code to break if new hash types are
+/// included in newer versions of this library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    MD5,
+    SHA2_256,
+    SHA2_512,
+    SHA3_256,
+    SHA3_512,
+    BLAKE2S,
+    BLAKE2B,
+    BLAKE3,
+    SHA1,
+    SHA2_224,
+    SHA2_384,
+    SHA2_512_256,
+    SHA3_224,
+    SHA3_384,
+    KECCAK256,
+    RIPEMD160,
+    WHIRLPOOL,
+}
+
+const ALL_ALGORITHMS: [HashAlgorithm; 17] = [
+    HashAlgorithm::MD5,
+    HashAlgorithm::SHA2_256,
+    HashAlgorithm::SHA2_512,
+    HashAlgorithm::SHA3_256,
+    HashAlgorithm::SHA3_512,
+    HashAlgorithm::BLAKE2S,
+    HashAlgorithm::BLAKE2B,
+    HashAlgorithm::BLAKE3,
+    HashAlgorithm::SHA1,
+    HashAlgorithm::SHA2_224,
+    HashAlgorithm::SHA2_384,
+    HashAlgorithm::SHA2_512_256,
+    HashAlgorithm::SHA3_224,
+    HashAlgorithm::SHA3_384,
+    HashAlgorithm::KECCAK256,
+    HashAlgorithm::RIPEMD160,
+    HashAlgorithm::WHIRLPOOL,
+];
+
+impl HashAlgorithm {
+    /// All the hash algorithms, in the order they were added to this library.
+    pub fn all() -> &'static [HashAlgorithm] {
+        &ALL_ALGORITHMS
+    }
+
+    /// The canonical name of the algorithm, as used by `Display`.
+    ///
+    /// The names follow the multicodec table, for example `sha2-256`, `sha3-512` or `blake3`.
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::MD5 => "md5",
+            HashAlgorithm::SHA1 => "sha1",
+            HashAlgorithm::SHA2_224 => "sha2-224",
+            HashAlgorithm::SHA2_256 => "sha2-256",
+            HashAlgorithm::SHA2_384 => "sha2-384",
+            HashAlgorithm::SHA2_512 => "sha2-512",
+            HashAlgorithm::SHA2_512_256 => "sha2-512-256",
+            HashAlgorithm::SHA3_224 => "sha3-224",
+            HashAlgorithm::SHA3_256 => "sha3-256",
+            HashAlgorithm::SHA3_384 => "sha3-384",
+            HashAlgorithm::SHA3_512 => "sha3-512",
+            HashAlgorithm::KECCAK256 => "keccak-256",
+            HashAlgorithm::BLAKE2S => "blake2s-256",
+            HashAlgorithm::BLAKE2B => "blake2b-512",
+            HashAlgorithm::BLAKE3 => "blake3",
+            HashAlgorithm::RIPEMD160 => "ripemd-160",
+            HashAlgorithm::WHIRLPOOL => "whirlpool",
+        }
+    }
+
+    /// The length of the digest in bytes.
+    pub fn output_len(self) -> usize {
+        match self {
+            HashAlgorithm::MD5 => 16,
+            HashAlgorithm::SHA1 | HashAlgorithm::RIPEMD160 => 20,
+            HashAlgorithm::SHA2_224 | HashAlgorithm::SHA3_224 => 28,
+            HashAlgorithm::SHA2_256 | HashAlgorithm::SHA2_512_256 | HashAlgorithm::SHA3_256 | HashAlgorithm::KECCAK256 => 32,
+            HashAlgorithm::SHA2_384 | HashAlgorithm::SHA3_384 => 48,
+            HashAlgorithm::SHA2_512 | HashAlgorithm::SHA3_512 | HashAlgorithm::WHIRLPOOL => 64,
+            HashAlgorithm::BLAKE2S => 32,
+            HashAlgorithm::BLAKE2B => 64,
+            HashAlgorithm::BLAKE3 => 32,
+        }
+    }
+
+    /// The length of the blocks the algorithm processes, in bytes.
+    ///
+    /// For the SHA-3 family and Keccak this is the rate of the sponge. This is the block size
+    /// HMAC uses to pad the key.
+    pub fn block_size(self) -> usize {
+        match self {
+            HashAlgorithm::MD5 | HashAlgorithm::SHA1 | HashAlgorithm::RIPEMD160 | HashAlgorithm::WHIRLPOOL => 64,
+            HashAlgorithm::SHA2_224 | HashAlgorithm::SHA2_256 => 64,
+            HashAlgorithm::SHA2_384 | HashAlgorithm::SHA2_512 | HashAlgorithm::SHA2_512_256 => 128,
+            HashAlgorithm::SHA3_224 => 144,
+            HashAlgorithm::SHA3_256 | HashAlgorithm::KECCAK256 => 136,
+            HashAlgorithm::SHA3_384 => 104,
+            HashAlgorithm::SHA3_512 => 72,
+            HashAlgorithm::BLAKE2S => 64,
+            HashAlgorithm::BLAKE2B => 128,
+            HashAlgorithm::BLAKE3 => 64,
+        }
+    }
+
+    /// Whether practical collision attacks are known against the algorithm.
+    ///
+    /// This is true for MD5 and SHA-1, which should only be used to interoperate with
+    /// systems that require them.
+    pub fn is_broken(self) -> bool {
+        matches!(self, HashAlgorithm::MD5 | HashAlgorithm::SHA1)
+    }
+
+    /// The name of the algorithm in the IANA "Hash Function Textual Names" registry, or in
+    /// the "Named Information Hash Algorithm" registry for the SHA-3 family.
+    ///
+    /// Returns `None` for algorithms that are in neither registry.
+    pub fn iana_name(self) -> Option<&'static str> {
+        match self {
+            HashAlgorithm::MD5 => Some("md5"),
+            HashAlgorithm::SHA1 => Some("sha-1"),
+            HashAlgorithm::SHA2_224 => Some("sha-224"),
+            HashAlgorithm::SHA2_256 => Some("sha-256"),
+            HashAlgorithm::SHA2_384 => Some("sha-384"),
+            HashAlgorithm::SHA2_512 => Some("sha-512"),
+            HashAlgorithm::SHA3_224 => Some("sha3-224"),
+            HashAlgorithm::SHA3_256 => Some("sha3-256"),
+            HashAlgorithm::SHA3_384 => Some("sha3-384"),
+            HashAlgorithm::SHA3_512 => Some("sha3-512"),
+            _ => None,
+        }
+    }
+
+    /// The code of the algorithm in the multicodec table, as used by multihash.
+    ///
+    /// Returns `None` for algorithms that have no code.
+    pub fn multicodec(self) -> Option<u64> {
+        match self {
+            HashAlgorithm::MD5 => Some(0xd5),
+            HashAlgorithm::SHA1 => Some(0x11),
+            HashAlgorithm::SHA2_224 => Some(0x1013),
+            HashAlgorithm::SHA2_256 => Some(0x12),
+            HashAlgorithm::SHA2_384 => Some(0x20),
+            HashAlgorithm::SHA2_512 => Some(0x13),
+            HashAlgorithm::SHA2_512_256 => Some(0x1015),
+            HashAlgorithm::SHA3_224 => Some(0x17),
+            HashAlgorithm::SHA3_256 => Some(0x16),
+            HashAlgorithm::SHA3_384 => Some(0x15),
+            HashAlgorithm::SHA3_512 => Some(0x14),
+            HashAlgorithm::KECCAK256 => Some(0x1b),
+            HashAlgorithm::BLAKE2S => Some(0xb260),
+            HashAlgorithm::BLAKE2B => Some(0xb240),
+            HashAlgorithm::BLAKE3 => Some(0x1e),
+            HashAlgorithm::RIPEMD160 => Some(0x1053),
+            HashAlgorithm::WHIRLPOOL => None,
+        }
+    }
+
+    /// The algorithm with the given multicodec code, if this library supports it.
+    pub fn from_multicodec(code: u64) -> Option<HashAlgorithm> {
+        ALL_ALGORITHMS.iter().cloned().find(|algorithm| algorithm.multicodec() == Some(code))
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl str::FromStr for HashAlgorithm {
+    type Err = ParseAlgorithmError;
+
+    /// Parses the common spellings of the algorithm names.
+    ///
+    /// Case, `-`, `_`, `/` and spaces are ignored, so `sha256`, `SHA2-256`, `sha-256` and
+    /// `SHA_256` all give `SHA2_256`. SHA-2 names may leave out the `2`, BLAKE2 names may
+    /// leave out the output size, and the IANA names are accepted too.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let normalized: String = input.chars()
+            .filter(|c| !['-', '_', '/', ' '].contains(c))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let algorithm = match normalized.as_str() {
+            "md5" => HashAlgorithm::MD5,
+            "sha1" => HashAlgorithm::SHA1,
+            "sha224" | "sha2224" => HashAlgorithm::SHA2_224,
+            "sha256" | "sha2256" => HashAlgorithm::SHA2_256,
+            "sha384" | "sha2384" => HashAlgorithm::SHA2_384,
+            "sha512" | "sha2512" => HashAlgorithm::SHA2_512,
+            "sha512256" | "sha2512256" => HashAlgorithm::SHA2_512_256,
+            "sha3224" => HashAlgorithm::SHA3_224,
+            "sha3256" => HashAlgorithm::SHA3_256,
+            "sha3384" => HashAlgorithm::SHA3_384,
+            "sha3512" => HashAlgorithm::SHA3_512,
+            "keccak256" => HashAlgorithm::KECCAK256,
+            "blake2s" | "blake2s256" => HashAlgorithm::BLAKE2S,
+            "blake2b" | "blake2b512" => HashAlgorithm::BLAKE2B,
+            "blake3" => HashAlgorithm::BLAKE3,
+            "ripemd160" | "rmd160" => HashAlgorithm::RIPEMD160,
+            "whirlpool" => HashAlgorithm::WHIRLPOOL,
+            _ => return Err(ParseAlgorithmError(input.to_string())),
+        };
+        Ok(algorithm)
+    }
+}
+
+/// The error returned when a string is not the name of a known hash algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAlgorithmError(pub String);
+
+impl fmt::Display for ParseAlgorithmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown hash algorithm: {:?}", self.0)
+    }
+}
+
+impl error::Error for ParseAlgorithmError {}
+
+/// The error returned when an operation is not available for the selected hash algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedAlgorithm(pub HashAlgorithm);
+
+impl fmt::Display for UnsupportedAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "operation not supported for {:?}", self.0)
+    }
+}
+
+impl error::Error for UnsupportedAlgorithm {}
+
+/// Hashes the given bytes with the selected hash algorithm.
+pub fn hash_bytes(hash_algorithm: HashAlgorithm, input: &[u8]) -> Digest {
+    let mut hasher = Hasher::new(hash_algorithm);
+    hasher.update(input);
+    hasher.finalize()
+}
+
+/// Hashes the given string with the selected hash algorithm.
+pub fn hash_string(hash_algorithm: HashAlgorithm, input: &str) -> Digest {
+    hash_bytes(hash_algorithm, input.as_bytes())
+}
+
+const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Reads the next chunk of the stream, retrying reads that were interrupted.
+fn read_chunk(stream: &mut dyn io::Read, buffer: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match stream.read(buffer) {
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+fn hash_stream_buffered(hash_algorithm: HashAlgorithm, stream: &mut dyn io::Read, buffer_size: usize) -> Result<Digest, io::Error> {
+    let mut hasher = Hasher::new(hash_algorithm);
+    let mut buffer = vec![0; buffer_size];
+    loop {
+        let read = read_chunk(stream, &mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+fn hash_stream(hash_algorithm: HashAlgorithm, stream: &mut dyn io::Read) -> Result<Digest, io::Error> {
+    hash_stream_buffered(hash_algorithm, stream, DEFAULT_BUFFER_SIZE)
+}
+
+/// Hashes everything that can be read from the given reader with the selected hash algorithm.
+///
+/// The reader is read until EOF. Returns an `io::Error` if any read fails.
+pub fn hash_reader<R: io::Read>(hash_algorithm: HashAlgorithm, mut reader: R) -> Result<Digest, io::Error> {
+    hash_stream(hash_algorithm, &mut reader)
+}
+
+/// Hashes the standard input of the process with the selected hash algorithm.
+///
+/// The standard input is read until EOF.
+pub fn hash_stdin(hash_algorithm: HashAlgorithm) -> Result<Digest, io::Error> {
+    let stdin = io::stdin();
+    let mut handle = stdin.lock();
+    hash_stream(hash_algorithm, &mut handle)
+}
+
+/// Hashes the given file with the selected hash algorithm.
+///
+/// Large files hashed with BLAKE3 are memory mapped and hashed on multiple threads, see
+/// `HashOptions` for the details.
+///
+/// Returns an `io::Error` if the file cannot be opened for any reason.
+pub fn hash_file<P: AsRef<Path>>(hash_algorithm: HashAlgorithm, path: P) -> Result<Digest, io::Error> {
+    hash_file_with_options(hash_algorithm, path, &HashOptions::default())
+}
@@ -0,0 +1,93 @@
+use std::{fs, io, thread};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::mpsc;
+
+use super::{read_chunk, Digest, HashAlgorithm, Hasher, DEFAULT_BUFFER_SIZE};
+
+const CHANNEL_DEPTH: usize = 4;
+
+fn multi_stream(hash_algorithms: &[HashAlgorithm], stream: &mut dyn io::Read) -> io::Result<Vec<Digest>> {
+    let mut hashers: Vec<Hasher> = hash_algorithms.iter().map(|&algorithm| Hasher::new(algorithm)).collect();
+    let mut buffer = vec![0; DEFAULT_BUFFER_SIZE];
+    loop {
+        let read = read_chunk(stream, &mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        for hasher in hashers.iter_mut() {
+            hasher.update(&buffer[..read]);
+        }
+    }
+    Ok(hashers.iter().map(Hasher::finalize).collect())
+}
+
+fn multi_stream_threaded(hash_algorithms: &[HashAlgorithm], stream: &mut dyn io::Read) -> io::Result<Vec<Digest>> {
+    let mut senders = Vec::with_capacity(hash_algorithms.len());
+    let mut workers = Vec::with_capacity(hash_algorithms.len());
+    for &algorithm in hash_algorithms {
+        let (sender, receiver) = mpsc::sync_channel::<Arc<Vec<u8>>>(CHANNEL_DEPTH);
+        senders.push(sender);
+        workers.push(thread::spawn(move || {
+            let mut hasher = Hasher::new(algorithm);
+            for chunk in receiver {
+                hasher.update(&chunk);
+            }
+            hasher.finalize()
+        }));
+    }
+
+    let mut result = Ok(());
+    loop {
+        let mut buffer = vec![0; DEFAULT_BUFFER_SIZE];
+        match read_chunk(stream, &mut buffer) {
+            Ok(0) => break,
+            Ok(read) => {
+                buffer.truncate(read);
+                let chunk = Arc::new(buffer);
+                for sender in senders.iter() {
+                    // A worker only stops receiving if it panicked, which is reported on join.
+                    let _ = sender.send(Arc::clone(&chunk));
+                }
+            }
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+    drop(senders);
+
+    let digests = workers.into_iter()
+        .map(|worker| worker.join().map_err(|_| io::Error::other("hashing thread panicked")))
+        .collect::<io::Result<Vec<Digest>>>()?;
+    result.map(|_| digests)
+}
+
+/// Hashes everything that can be read from the given reader with every one of the selected
+/// hash algorithms, reading the data only once.
+///
+/// The digests are returned in the same order as `hash_algorithms`.
+pub fn hash_reader_multi<R: io::Read>(hash_algorithms: &[HashAlgorithm], mut reader: R) -> io::Result<Vec<Digest>> {
+    multi_stream(hash_algorithms, &mut reader)
+}
+
+/// Hashes the given file with every one of the selected hash algorithms, reading the file
+/// only once.
+///
+/// The digests are returned in the same order as `hash_algorithms`.
+///
+/// Returns an `io::Error` if the file cannot be opened for any reason.
+pub fn hash_file_multi<P: AsRef<Path>>(hash_algorithms: &[HashAlgorithm], path: P) -> io::Result<Vec<Digest>> {
+    let mut file = fs::File::open(path)?;
+    multi_stream(hash_algorithms, &mut file)
+}
+
+/// Same as `hash_file_multi`, but every hash algorithm runs on its own thread while the
+/// file is being read.
+///
+/// This is worth it for large files when several slow hash algorithms are selected.
+pub fn hash_file_multi_threaded<P: AsRef<Path>>(hash_algorithms: &[HashAlgorithm], path: P) -> io::Result<Vec<Digest>> {
+    let mut file = fs::File::open(path)?;
+    multi_stream_threaded(hash_algorithms, &mut file)
+}
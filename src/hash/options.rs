@@ -0,0 +1,236 @@
+use std::{fmt, fs, io};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{read_chunk, Digest, HashAlgorithm, Hasher, DEFAULT_BUFFER_SIZE};
+
+/// Files smaller than this are always streamed, even when memory mapping is enabled.
+#[cfg(feature = "mmap")]
+const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+/// How much of a memory mapped file is hashed between progress reports.
+#[cfg(feature = "mmap")]
+const MMAP_PIECE: usize = 16 * 1024 * 1024;
+
+type ProgressCallback = Arc<dyn Fn(u64, Option<u64>) + Send + Sync>;
+
+/// A token to cancel hashing from another thread.
+///
+/// Clones share the same state, so one clone can be given to `HashOptions::cancel_token`
+/// while another is kept to call `cancel`.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the hashing using this token, or any of its clones.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if `cancel` was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Options for hashing files.
+///
+/// ```
+/// use estahr::hash::{CancelToken, HashOptions};
+///
+/// let token = CancelToken::new();
+/// let options = HashOptions::new()
+///     .buffer_size(1024 * 1024)
+///     .mmap(false)
+///     .progress(|done, total| eprintln!("{} of {:?} bytes", done, total))
+///     .cancel_token(token.clone());
+/// ```
+#[derive(Clone)]
+pub struct HashOptions {
+    buffer_size: usize,
+    mmap: bool,
+    progress: Option<ProgressCallback>,
+    cancel_token: Option<CancelToken>,
+}
+
+impl Default for HashOptions {
+    fn default() -> Self {
+        HashOptions {
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: true,
+            progress: None,
+            cancel_token: None,
+        }
+    }
+}
+
+impl fmt::Debug for HashOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HashOptions")
+            .field("buffer_size", &self.buffer_size)
+            .field("mmap", &self.mmap)
+            .field("progress", &self.progress.is_some())
+            .field("cancel_token", &self.cancel_token)
+            .finish()
+    }
+}
+
+impl HashOptions {
+    /// The default options: a 64 KiB read buffer, with memory mapping enabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the size of the buffer used to read the file when it is streamed.
+    ///
+    /// A size of 0 is treated as 1.
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size.max(1);
+        self
+    }
+
+    /// Enables or disables memory mapping of large files.
+    ///
+    /// Memory mapping is only used for BLAKE3, which can then hash the whole file on
+    /// multiple threads. It is only attempted for regular files of at least 1 MiB; pipes,
+    /// devices and other special files are always streamed, and so is any file that fails to
+    /// map. Without the `mmap` cargo feature this option has no effect.
+    pub fn mmap(mut self, mmap: bool) -> Self {
+        self.mmap = mmap;
+        self
+    }
+
+    /// Sets a callback to report the progress, with the number of bytes hashed so far and
+    /// the size of the file (`None` if it is not a regular file).
+    ///
+    /// The callback is called after every buffer (every 16 MiB for memory mapped files), on
+    /// the hashing thread, so it should return quickly.
+    pub fn progress<F: Fn(u64, Option<u64>) + Send + Sync + 'static>(mut self, progress: F) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Sets a token to cancel the hashing. It is checked before every buffer, and hashing a
+    /// cancelled file fails with an `io::Error` of kind `Other`.
+    pub fn cancel_token(mut self, cancel_token: CancelToken) -> Self {
+        self.cancel_token = Some(cancel_token);
+        self
+    }
+
+    fn check_cancelled(&self) -> io::Result<()> {
+        match &self.cancel_token {
+            Some(token) if token.is_cancelled() => Err(io::Error::other("hashing cancelled")),
+            _ => Ok(()),
+        }
+    }
+
+    fn report(&self, done: u64, total: Option<u64>) {
+        if let Some(progress) = &self.progress {
+            progress(done, total);
+        }
+    }
+}
+
+/// The digest of a file with statistics on the hashing, returned by `hash_file_with_stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashReport {
+    /// The digest of the file.
+    pub digest: Digest,
+    /// The number of bytes hashed.
+    pub bytes_read: u64,
+    /// The time taken to hash the file, from opening it to the digest.
+    pub elapsed: Duration,
+}
+
+impl HashReport {
+    /// The throughput, in bytes per second.
+    pub fn throughput(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.bytes_read as f64 / seconds
+    }
+}
+
+#[cfg(feature = "mmap")]
+fn blake3_mmap(file: &fs::File, options: &HashOptions) -> Option<io::Result<(Digest, u64)>> {
+    let metadata = file.metadata().ok()?;
+    if !metadata.is_file() || metadata.len() < MMAP_THRESHOLD {
+        return None;
+    }
+    // Safety: the map is only read while hashing. If the file is truncated in the meantime
+    // the process can receive a SIGBUS, which is the usual caveat of memory mapping.
+    let map = unsafe { memmap2::Mmap::map(file) }.ok()?;
+    let total = map.len() as u64;
+    let mut hasher = blake3::Hasher::new();
+    let mut done = 0;
+    for piece in map.chunks(MMAP_PIECE) {
+        if let Err(e) = options.check_cancelled() {
+            return Some(Err(e));
+        }
+        hasher.update(piece);
+        done += piece.len() as u64;
+        options.report(done, Some(total));
+    }
+    Some(Ok((Digest::new(HashAlgorithm::BLAKE3, hasher.finalize().as_bytes().to_vec()), done)))
+}
+
+fn hash_file_stream(hash_algorithm: HashAlgorithm, file: &mut fs::File, options: &HashOptions) -> io::Result<(Digest, u64)> {
+    let total = file.metadata().ok().filter(|metadata| metadata.is_file()).map(|metadata| metadata.len());
+    let mut hasher = Hasher::new(hash_algorithm);
+    let mut buffer = vec![0; options.buffer_size];
+    let mut done = 0;
+    loop {
+        options.check_cancelled()?;
+        let read = read_chunk(file, &mut buffer)?;
+        if read == 0 {
+            return Ok((hasher.finalize(), done));
+        }
+        hasher.update(&buffer[..read]);
+        done += read as u64;
+        options.report(done, total);
+    }
+}
+
+/// Hashes the given file with the selected hash algorithm, using the given options.
+///
+/// Returns an `io::Error` if the file cannot be opened for any reason, or if the hashing is
+/// cancelled.
+pub fn hash_file_with_options<P: AsRef<Path>>(hash_algorithm: HashAlgorithm, path: P, options: &HashOptions) -> io::Result<Digest> {
+    hash_file_with_stats(hash_algorithm, path, options).map(|report| report.digest)
+}
+
+/// Hashes the given file like `hash_file_with_options`, and also returns the number of bytes
+/// hashed, the time it took and the throughput.
+///
+/// ```no_run
+/// use estahr::hash::{hash_file_with_stats, HashAlgorithm, HashOptions};
+///
+/// let report = hash_file_with_stats(HashAlgorithm::BLAKE3, "disk.img", &HashOptions::new()).unwrap();
+/// println!("{} ({} bytes at {:.0} MB/s)", report.digest, report.bytes_read, report.throughput() / 1e6);
+/// ```
+pub fn hash_file_with_stats<P: AsRef<Path>>(hash_algorithm: HashAlgorithm, path: P, options: &HashOptions) -> io::Result<HashReport> {
+    let start = Instant::now();
+    let mut file = fs::File::open(path)?;
+    #[cfg(feature = "mmap")]
+    let mapped = if options.mmap && hash_algorithm == HashAlgorithm::BLAKE3 {
+        blake3_mmap(&file, options)
+    } else {
+        None
+    };
+    #[cfg(not(feature = "mmap"))]
+    let mapped: Option<io::Result<(Digest, u64)>> = None;
+    let (digest, bytes_read) = match mapped {
+        Some(result) => result?,
+        None => hash_file_stream(hash_algorithm, &mut file, options)?,
+    };
+    Ok(HashReport { digest, bytes_read, elapsed: start.elapsed() })
+}
@@ -0,0 +1,160 @@
+use std::{error, fmt};
+
+use data_encoding::{BASE32, BASE64};
+
+use super::HashAlgorithm;
+
+/// The output of a hash function.
+///
+/// A `Digest` owns the raw bytes produced by the hash function and remembers which
+/// `HashAlgorithm` produced them. It can be encoded to (and parsed back from) hex, base64
+/// and base32, and to the self-describing Multihash, Subresource Integrity and `algo:hex`
+/// forms, which carry the algorithm along with the value.
+///
+/// The `==` operator compares the algorithm and the bytes like any other value. When one of
+/// the sides comes from an untrusted source (a MAC, a token, ...) use `ct_eq` instead, which
+/// runs in constant time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Digest {
+    algorithm: HashAlgorithm,
+    bytes: Vec<u8>,
+}
+
+/// The error returned when a `Digest` cannot be built from its encoded form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigestParseError {
+    /// The input is not valid in the requested encoding.
+    InvalidEncoding(String),
+    /// The decoded bytes do not have the output length of the hash algorithm.
+    InvalidLength { expected: usize, actual: usize },
+    /// The algorithm named (or numbered) in a self-describing digest is not known.
+    UnknownAlgorithm(String),
+}
+
+impl fmt::Display for DigestParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DigestParseError::InvalidEncoding(reason) => write!(f, "invalid digest encoding: {}", reason),
+            DigestParseError::InvalidLength { expected, actual } => {
+                write!(f, "invalid digest length: expected {} bytes, got {}", expected, actual)
+            }
+            DigestParseError::UnknownAlgorithm(name) => write!(f, "unknown digest algorithm: {}", name),
+        }
+    }
+}
+
+impl error::Error for DigestParseError {}
+
+impl Digest {
+    pub(crate) fn new(algorithm: HashAlgorithm, bytes: Vec<u8>) -> Digest {
+        Digest { algorithm, bytes }
+    }
+
+    /// Builds a digest from the raw output bytes of the given hash algorithm.
+    ///
+    /// Returns an error if the length of `bytes` does not match the output length of the
+    /// algorithm.
+    pub fn from_bytes(algorithm: HashAlgorithm, bytes: &[u8]) -> Result<Digest, DigestParseError> {
+        let expected = algorithm.output_len();
+        if bytes.len() != expected {
+            return Err(DigestParseError::InvalidLength { expected, actual: bytes.len() });
+        }
+        Ok(Digest::new(algorithm, bytes.to_vec()))
+    }
+
+    /// Parses a hex encoded digest (upper or lower case).
+    pub fn from_hex(algorithm: HashAlgorithm, input: &str) -> Result<Digest, DigestParseError> {
+        let bytes = hex::decode(input).map_err(|e| DigestParseError::InvalidEncoding(e.to_string()))?;
+        Digest::from_bytes(algorithm, &bytes)
+    }
+
+    /// Parses a base64 encoded digest (standard alphabet, with padding).
+    pub fn from_base64(algorithm: HashAlgorithm, input: &str) -> Result<Digest, DigestParseError> {
+        let bytes = BASE64.decode(input.as_bytes())
+            .map_err(|e| DigestParseError::InvalidEncoding(e.to_string()))?;
+        Digest::from_bytes(algorithm, &bytes)
+    }
+
+    /// Parses a base32 encoded digest (RFC 4648 alphabet, with padding).
+    pub fn from_base32(algorithm: HashAlgorithm, input: &str) -> Result<Digest, DigestParseError> {
+        let bytes = BASE32.decode(input.as_bytes())
+            .map_err(|e| DigestParseError::InvalidEncoding(e.to_string()))?;
+        Digest::from_bytes(algorithm, &bytes)
+    }
+
+    /// The hash algorithm that produced this digest.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// The raw bytes of the digest.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Consumes the digest and returns its raw bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// The length of the digest in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns true if the digest has no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Encodes the digest as lower case hex.
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.bytes)
+    }
+
+    /// Encodes the digest as upper case hex.
+    pub fn to_hex_upper(&self) -> String {
+        hex::encode_upper(&self.bytes)
+    }
+
+    /// Encodes the digest as base64 (standard alphabet, with padding).
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(&self.bytes)
+    }
+
+    /// Encodes the digest as base32 (RFC 4648 alphabet, with padding).
+    pub fn to_base32(&self) -> String {
+        BASE32.encode(&self.bytes)
+    }
+
+    /// Compares two digests in constant time.
+    ///
+    /// Only the algorithm and the length of the digests can leak through timing.
+    pub fn ct_eq(&self, other: &Digest) -> bool {
+        self.algorithm == other.algorithm && constant_time_eq::constant_time_eq(&self.bytes, &other.bytes)
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::LowerHex for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::UpperHex for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_hex_upper())
+    }
+}
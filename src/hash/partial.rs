@@ -0,0 +1,118 @@
+use std::{fmt, fs, io};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::{read_chunk, Digest, HashAlgorithm, Hasher, DEFAULT_BUFFER_SIZE};
+
+/// A sampled digest of a file, returned by `hash_file_sampled`.
+///
+/// It is deliberately not a `Digest`: a sampled digest only covers part of the file, so it
+/// cannot be passed to `verify_file`, written to a manifest or encoded in the self-describing
+/// forms. It can only be compared with other sampled digests.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SampledDigest(Digest);
+
+impl SampledDigest {
+    /// The hash algorithm that produced this digest.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.0.algorithm()
+    }
+
+    /// The raw bytes of the digest.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+
+    /// Encodes the digest as lower case hex.
+    pub fn to_hex(&self) -> String {
+        self.0.to_hex()
+    }
+}
+
+impl fmt::Display for SampledDigest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// Hashes exactly `length` bytes read from the current position of the file.
+fn hash_exact(hasher: &mut Hasher, file: &mut fs::File, mut length: u64, buffer: &mut [u8]) -> io::Result<()> {
+    while length > 0 {
+        let want = length.min(buffer.len() as u64) as usize;
+        let read = read_chunk(file, &mut buffer[..want])?;
+        if read == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the file ended before the end of the range"));
+        }
+        hasher.update(&buffer[..read]);
+        length -= read as u64;
+    }
+    Ok(())
+}
+
+/// Hashes `length` bytes of the given file, starting at byte `offset`, with the selected hash
+/// algorithm.
+///
+/// The digest is the same as hashing those bytes on their own. Returns an `io::Error` if the
+/// file cannot be opened, or of kind `UnexpectedEof` if the range goes past the end of the
+/// file.
+pub fn hash_file_range<P: AsRef<Path>>(hash_algorithm: HashAlgorithm, path: P, offset: u64, length: u64) -> io::Result<Digest> {
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut hasher = Hasher::new(hash_algorithm);
+    let mut buffer = vec![0; DEFAULT_BUFFER_SIZE.min(length.max(1) as usize)];
+    hash_exact(&mut hasher, &mut file, length, &mut buffer)?;
+    Ok(hasher.finalize())
+}
+
+/// Computes a sampled digest of the given file: a quick fingerprint that only reads the file
+/// size and `blocks` blocks of `block_size` bytes, spread evenly from the start to the end.
+///
+/// **A sampled digest is not an integrity guarantee.** Changes to the bytes between the
+/// samples are not detected, and anyone can craft two different files with the same sampled
+/// digest. Use it to detect likely changes to huge files quickly (like a modification time
+/// with more certainty), and `hash_file` to verify them. It is returned as a `SampledDigest`,
+/// so it cannot be mistaken for the digest of the whole file.
+///
+/// The hashed input is the file size as 8 little endian bytes, followed by the blocks in
+/// order. With 3 blocks they are at the start, the middle and the end of the file; with 2,
+/// at the start and the end; with 1, at the start. Blocks never overlap: a file no larger
+/// than `blocks * block_size` bytes is hashed whole, after its size.
+///
+/// Returns an `io::Error` if the file cannot be read, or of kind `InvalidInput` if `blocks` or
+/// `block_size` is 0.
+///
+/// ```
+/// use estahr::hash::{hash_file_sampled, HashAlgorithm};
+///
+/// # let path = std::env::temp_dir().join("estahr_sampled_example.bin");
+/// # std::fs::write(&path, vec![1; 100_000]).unwrap();
+/// let digest = hash_file_sampled(HashAlgorithm::BLAKE3, &path, 3, 4096).unwrap();
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn hash_file_sampled<P: AsRef<Path>>(hash_algorithm: HashAlgorithm, path: P, blocks: usize,
+                                         block_size: usize) -> io::Result<SampledDigest> {
+    if blocks == 0 || block_size == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "the number of blocks and the block size must be positive"));
+    }
+    let mut file = fs::File::open(path)?;
+    let size = file.seek(SeekFrom::End(0))?;
+    let mut hasher = Hasher::new(hash_algorithm);
+    hasher.update(&size.to_le_bytes());
+    file.seek(SeekFrom::Start(0))?;
+
+    let sampled = (blocks as u64).saturating_mul(block_size as u64);
+    if size <= sampled {
+        let mut buffer = vec![0; DEFAULT_BUFFER_SIZE];
+        hash_exact(&mut hasher, &mut file, size, &mut buffer)?;
+        return Ok(SampledDigest(hasher.finalize()));
+    }
+    let mut buffer = vec![0; block_size];
+    let last = size - block_size as u64;
+    for i in 0..blocks as u64 {
+        let offset = if blocks == 1 { 0 } else { (u128::from(last) * u128::from(i) / (blocks as u128 - 1)) as u64 };
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+    Ok(SampledDigest(hasher.finalize()))
+}
@@ -0,0 +1,99 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+
+use super::{Digest, HashAlgorithm, Hasher, DEFAULT_BUFFER_SIZE};
+
+/// Hashes everything that can be read from the given async reader with the selected hash
+/// algorithm, without blocking the runtime.
+///
+/// The reader is read until EOF. Returns an `io::Error` if any read fails. Requires the
+/// `tokio` feature.
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// use estahr::hash::{hash_async_reader, hash_string, HashAlgorithm};
+///
+/// let digest = hash_async_reader(HashAlgorithm::SHA2_256, &b"hello world"[..]).await.unwrap();
+/// assert_eq!(digest, hash_string(HashAlgorithm::SHA2_256, "hello world"));
+/// # });
+/// ```
+pub async fn hash_async_reader<R: AsyncRead + Unpin>(hash_algorithm: HashAlgorithm, mut reader: R) -> io::Result<Digest> {
+    let mut hasher = Hasher::new(hash_algorithm);
+    let mut buffer = vec![0; DEFAULT_BUFFER_SIZE];
+    loop {
+        let read = match reader.read(&mut buffer).await {
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => result?,
+        };
+        if read == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+/// An `AsyncWrite` adapter that hashes everything written through it.
+///
+/// The bytes are passed on to the wrapped writer, and only the bytes it accepts are hashed.
+/// Use `tokio::io::sink()` as the writer to only hash. Requires the `tokio` feature.
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// use estahr::hash::{hash_string, AsyncHashingWriter, HashAlgorithm};
+///
+/// let mut writer = AsyncHashingWriter::new(HashAlgorithm::BLAKE3, Vec::new());
+/// tokio::io::copy(&mut &b"hello world"[..], &mut writer).await.unwrap();
+/// let (copy, digest) = writer.into_inner();
+/// assert_eq!(copy, b"hello world");
+/// assert_eq!(digest, hash_string(HashAlgorithm::BLAKE3, "hello world"));
+/// # });
+/// ```
+pub struct AsyncHashingWriter<W> {
+    inner: W,
+    hasher: Hasher,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncHashingWriter<W> {
+    /// Wraps the writer, hashing with the selected hash algorithm.
+    pub fn new(hash_algorithm: HashAlgorithm, inner: W) -> AsyncHashingWriter<W> {
+        AsyncHashingWriter { inner, hasher: Hasher::new(hash_algorithm) }
+    }
+
+    /// The digest of everything written so far.
+    pub fn finalize(&self) -> Digest {
+        self.hasher.finalize()
+    }
+
+    /// The wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the wrapped writer and the digest of everything written through it.
+    pub fn into_inner(self) -> (W, Digest) {
+        let digest = self.hasher.finalize();
+        (self.inner, digest)
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncHashingWriter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            this.hasher.update(&buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,230 @@
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::{fs, io};
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+
+use super::{hash_bytes, hash_file, Digest, HashAlgorithm, Hasher};
+
+const ENTRY_FILE: u8 = b'f';
+const ENTRY_EXECUTABLE: u8 = b'x';
+const ENTRY_SYMLINK: u8 = b'l';
+
+const PATH_MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Options for hashing a directory tree with `hash_tree_with_options`.
+///
+/// ```
+/// use estahr::hash::TreeOptions;
+///
+/// let options = TreeOptions::new()
+///     .follow_symlinks(true)
+///     .ignore("*.tmp")
+///     .ignore("target");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    follow_symlinks: bool,
+    executable_bit: bool,
+    ignore: Vec<String>,
+}
+
+impl TreeOptions {
+    /// The default options: symlinks are not followed, the executable bit is not included and
+    /// nothing is ignored.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When true, symlinks are followed and hashed as the file or directory they point to.
+    ///
+    /// When false, a symlink is hashed as its own entry, whose content is the path it points
+    /// to.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// When true, whether a file is executable becomes part of the digest.
+    ///
+    /// This only has an effect on Unix, where a file is executable if any of its execute
+    /// permission bits is set.
+    pub fn executable_bit(mut self, executable_bit: bool) -> Self {
+        self.executable_bit = executable_bit;
+        self
+    }
+
+    /// Adds a glob pattern of files and directories to leave out of the digest.
+    ///
+    /// A pattern without a `/` is matched against the name of every entry, at any depth. A
+    /// pattern with a `/` is matched against the path of the entry relative to the root,
+    /// using `/` as separator. An ignored directory is skipped with everything under it.
+    pub fn ignore(mut self, pattern: &str) -> Self {
+        self.ignore.push(pattern.to_string());
+        self
+    }
+}
+
+struct Walker<'a> {
+    options: &'a TreeOptions,
+    name_patterns: Vec<Pattern>,
+    path_patterns: Vec<Pattern>,
+    hash_algorithm: HashAlgorithm,
+    hasher: Hasher,
+    /// The canonical paths of the directories being walked, used to detect symlink loops.
+    ancestors: Vec<PathBuf>,
+}
+
+/// The bytes of a file name, as the file system stores them on Unix.
+#[cfg(unix)]
+fn name_bytes(name: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(name.as_bytes())
+}
+
+/// The bytes of a file name, in UTF-8. Names that are not valid Unicode are converted lossily.
+#[cfg(not(unix))]
+fn name_bytes(name: &OsStr) -> Cow<'_, [u8]> {
+    match name.to_string_lossy() {
+        Cow::Borrowed(name) => Cow::Borrowed(name.as_bytes()),
+        Cow::Owned(name) => Cow::Owned(name.into_bytes()),
+    }
+}
+
+fn path_bytes(relative: &Path) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (i, component) in relative.components().enumerate() {
+        if i > 0 {
+            bytes.push(b'/');
+        }
+        bytes.extend_from_slice(&name_bytes(component.as_os_str()));
+    }
+    bytes
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+impl<'a> Walker<'a> {
+    fn is_ignored(&self, relative: &Path) -> bool {
+        let name = relative.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let path = String::from_utf8_lossy(&path_bytes(relative)).into_owned();
+        self.name_patterns.iter().any(|pattern| pattern.matches(&name))
+            || self.path_patterns.iter().any(|pattern| pattern.matches_with(&path, PATH_MATCH_OPTIONS))
+    }
+
+    fn add_entry(&mut self, relative: &Path, kind: u8, digest: &Digest) {
+        let path = path_bytes(relative);
+        self.hasher.update(&(path.len() as u64).to_le_bytes());
+        self.hasher.update(&path);
+        self.hasher.update(&[kind]);
+        self.hasher.update(digest.as_bytes());
+    }
+
+    fn walk(&mut self, root: &Path, relative: &Path) -> io::Result<()> {
+        let directory = root.join(relative);
+        let canonical = fs::canonicalize(&directory)?;
+        if self.ancestors.contains(&canonical) {
+            return Err(io::Error::other(format!("symlink loop at {}", directory.display())));
+        }
+        self.ancestors.push(canonical);
+
+        let mut names: Vec<_> = fs::read_dir(&directory)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<io::Result<_>>()?;
+        names.sort();
+
+        for name in names {
+            let relative = relative.join(&name);
+            if self.is_ignored(&relative) {
+                continue;
+            }
+            let path = root.join(&relative);
+            let link_metadata = fs::symlink_metadata(&path)?;
+            if link_metadata.file_type().is_symlink() && !self.options.follow_symlinks {
+                let target = fs::read_link(&path)?;
+                let digest = hash_bytes(self.hash_algorithm, &path_bytes(&target));
+                self.add_entry(&relative, ENTRY_SYMLINK, &digest);
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            if metadata.is_dir() {
+                self.walk(root, &relative)?;
+            } else if metadata.is_file() {
+                let kind = if self.options.executable_bit && is_executable(&metadata) {
+                    ENTRY_EXECUTABLE
+                } else {
+                    ENTRY_FILE
+                };
+                let digest = hash_file(self.hash_algorithm, &path)?;
+                self.add_entry(&relative, kind, &digest);
+            }
+        }
+
+        self.ancestors.pop();
+        Ok(())
+    }
+}
+
+/// Hashes a whole directory tree into a single digest, with the default `TreeOptions`.
+///
+/// See `hash_tree_with_options` for how the digest is computed.
+pub fn hash_tree<P: AsRef<Path>>(hash_algorithm: HashAlgorithm, dir: P) -> io::Result<Digest> {
+    hash_tree_with_options(hash_algorithm, dir, &TreeOptions::default())
+}
+
+/// Hashes a whole directory tree into a single digest.
+///
+/// The entries of every directory are visited sorted by name, so the digest only depends on
+/// the content of the tree, not on the order the file system lists it in. Every file (and
+/// every symlink, unless they are followed) adds a record to the final hash made of:
+/// 1. the length of its path relative to `dir`, as a little endian `u64`,
+/// 2. that path, with `/` as separator. On Unix the names are hashed as the raw bytes the file
+///    system stores, so names that are not valid UTF-8 never collide. Elsewhere they are
+///    converted to UTF-8, and names that are not valid Unicode are replaced lossily (two such
+///    names can then hash the same),
+/// 3. one byte for the kind of entry: `f` for files, `x` for executable files and `l` for
+///    symlinks,
+/// 4. the digest of the file content (or of the symlink target path).
+///
+/// Empty directories, and entries that are neither files, directories nor symlinks, do not
+/// change the digest.
+///
+/// Returns an `io::Error` if any entry cannot be read, if an ignore pattern is invalid, or if
+/// followed symlinks form a loop.
+pub fn hash_tree_with_options<P: AsRef<Path>>(hash_algorithm: HashAlgorithm, dir: P, options: &TreeOptions) -> io::Result<Digest> {
+    let mut name_patterns = Vec::new();
+    let mut path_patterns = Vec::new();
+    for pattern in options.ignore.iter() {
+        let compiled = Pattern::new(pattern).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if pattern.contains('/') {
+            path_patterns.push(compiled);
+        } else {
+            name_patterns.push(compiled);
+        }
+    }
+
+    let mut walker = Walker {
+        options,
+        name_patterns,
+        path_patterns,
+        hash_algorithm,
+        hasher: Hasher::new(hash_algorithm),
+        ancestors: Vec::new(),
+    };
+    walker.walk(dir.as_ref(), Path::new(""))?;
+    Ok(walker.hasher.finalize())
+}
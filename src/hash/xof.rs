@@ -0,0 +1,287 @@
+use std::{fmt, io};
+
+use digest::{ExtendableOutput, Input, XofReader as _};
+use sha3::{Sha3XofReader, Shake128, Shake256};
+
+const SHAKE128_RATE: usize = 168;
+const SHAKE256_RATE: usize = 136;
+const CSHAKE_PAD: u8 = 0x04;
+
+/// This represents the type of extendable-output function (XOF).
+///
+/// Unlike a `HashAlgorithm`, an XOF can produce as many output bytes as needed. Reading
+/// more output does not change the bytes already read: the first 32 bytes of a 64 byte output
+/// are the 32 byte output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XofAlgorithm {
+    BLAKE3,
+    SHAKE128,
+    SHAKE256,
+}
+
+/// A Keccak sponge for cSHAKE, which the sha3 crate does not provide.
+#[derive(Clone)]
+struct Sponge {
+    state: [u64; 25],
+    rate: usize,
+    offset: usize,
+}
+
+impl Sponge {
+    fn new(rate: usize) -> Sponge {
+        Sponge { state: [0; 25], rate, offset: 0 }
+    }
+
+    fn xor_byte(&mut self, index: usize, byte: u8) {
+        self.state[index / 8] ^= u64::from(byte) << (8 * (index % 8));
+    }
+
+    fn absorb(&mut self, input: &[u8]) {
+        for &byte in input {
+            self.xor_byte(self.offset, byte);
+            self.offset += 1;
+            if self.offset == self.rate {
+                keccak::f1600(&mut self.state);
+                self.offset = 0;
+            }
+        }
+    }
+
+    /// Pads the last block and permutes, leaving the sponge ready to squeeze from offset 0.
+    fn pad(&mut self, padding: u8) {
+        self.xor_byte(self.offset, padding);
+        self.xor_byte(self.rate - 1, 0x80);
+        keccak::f1600(&mut self.state);
+        self.offset = 0;
+    }
+
+    fn squeeze(&mut self, output: &mut [u8]) {
+        for byte in output.iter_mut() {
+            if self.offset == self.rate {
+                keccak::f1600(&mut self.state);
+                self.offset = 0;
+            }
+            *byte = (self.state[self.offset / 8] >> (8 * (self.offset % 8))) as u8;
+            self.offset += 1;
+        }
+    }
+}
+
+/// `left_encode` from NIST SP 800-185.
+fn left_encode(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&byte| byte == 0).count().min(7);
+    let mut encoded = vec![(8 - skip) as u8];
+    encoded.extend_from_slice(&bytes[skip..]);
+    encoded
+}
+
+/// `encode_string` from NIST SP 800-185.
+fn encode_string(input: &[u8]) -> Vec<u8> {
+    let mut encoded = left_encode(input.len() as u64 * 8);
+    encoded.extend_from_slice(input);
+    encoded
+}
+
+#[derive(Clone)]
+enum Inner {
+    BLAKE3(Box<blake3::Hasher>),
+    SHAKE128(Shake128),
+    SHAKE256(Shake256),
+    Sponge(Sponge),
+}
+
+/// An incremental extendable-output hasher.
+///
+/// Data is fed with `update` (or through `io::Write`), then `finalize_xof` returns a reader
+/// that produces the output.
+///
+/// ```
+/// use std::io::Read;
+/// use estahr::hash::{Xof, XofAlgorithm};
+///
+/// let mut xof = Xof::new(XofAlgorithm::SHAKE128);
+/// xof.update(b"abc");
+/// let mut output = [0; 16];
+/// xof.finalize_xof().read_exact(&mut output).unwrap();
+/// assert_eq!(output, [0x58, 0x81, 0x09, 0x2d, 0xd8, 0x18, 0xbf, 0x5c, 0xf8, 0xa3, 0xdd, 0xb7, 0x93, 0xfb, 0xcb, 0xa7]);
+/// ```
+#[derive(Clone)]
+pub struct Xof {
+    algorithm: XofAlgorithm,
+    inner: Inner,
+}
+
+impl Xof {
+    /// Creates a new extendable-output hasher for the selected algorithm.
+    pub fn new(xof_algorithm: XofAlgorithm) -> Xof {
+        let inner = match xof_algorithm {
+            XofAlgorithm::BLAKE3 => Inner::BLAKE3(Box::new(blake3::Hasher::new())),
+            XofAlgorithm::SHAKE128 => Inner::SHAKE128(Shake128::default()),
+            XofAlgorithm::SHAKE256 => Inner::SHAKE256(Shake256::default()),
+        };
+        Xof { algorithm: xof_algorithm, inner }
+    }
+
+    /// Creates a cSHAKE128 hasher (NIST SP 800-185) with the given function name and
+    /// customization string.
+    ///
+    /// With both strings empty, this is the same as SHAKE128.
+    pub fn cshake128(function_name: &[u8], customization: &[u8]) -> Xof {
+        Xof::cshake(XofAlgorithm::SHAKE128, SHAKE128_RATE, function_name, customization)
+    }
+
+    /// Creates a cSHAKE256 hasher (NIST SP 800-185) with the given function name and
+    /// customization string.
+    ///
+    /// With both strings empty, this is the same as SHAKE256.
+    pub fn cshake256(function_name: &[u8], customization: &[u8]) -> Xof {
+        Xof::cshake(XofAlgorithm::SHAKE256, SHAKE256_RATE, function_name, customization)
+    }
+
+    fn cshake(xof_algorithm: XofAlgorithm, rate: usize, function_name: &[u8], customization: &[u8]) -> Xof {
+        if function_name.is_empty() && customization.is_empty() {
+            return Xof::new(xof_algorithm);
+        }
+        // bytepad(encode_string(N) || encode_string(S), rate)
+        let mut prefix = left_encode(rate as u64);
+        prefix.extend(encode_string(function_name));
+        prefix.extend(encode_string(customization));
+        let padded_len = prefix.len().div_ceil(rate) * rate;
+        prefix.resize(padded_len, 0);
+
+        let mut sponge = Sponge::new(rate);
+        sponge.absorb(&prefix);
+        Xof { algorithm: xof_algorithm, inner: Inner::Sponge(sponge) }
+    }
+
+    /// The algorithm used by this hasher.
+    pub fn algorithm(&self) -> XofAlgorithm {
+        self.algorithm
+    }
+
+    /// Feeds more data into the hasher.
+    pub fn update(&mut self, input: &[u8]) {
+        match &mut self.inner {
+            Inner::BLAKE3(hasher) => {
+                hasher.update(input);
+            }
+            Inner::SHAKE128(hasher) => hasher.input(input),
+            Inner::SHAKE256(hasher) => hasher.input(input),
+            Inner::Sponge(sponge) => sponge.absorb(input),
+        }
+    }
+
+    /// Returns a reader over the output for all the data fed so far.
+    ///
+    /// The hasher is left untouched, so more data can still be fed after this.
+    pub fn finalize_xof(&self) -> XofReader {
+        let inner = match &self.inner {
+            Inner::BLAKE3(hasher) => ReaderInner::BLAKE3(hasher.finalize_xof()),
+            Inner::SHAKE128(hasher) => ReaderInner::SHA3(hasher.clone().xof_result()),
+            Inner::SHAKE256(hasher) => ReaderInner::SHA3(hasher.clone().xof_result()),
+            Inner::Sponge(sponge) => {
+                let mut sponge = sponge.clone();
+                sponge.pad(CSHAKE_PAD);
+                ReaderInner::Sponge(sponge)
+            }
+        };
+        XofReader { inner }
+    }
+
+    /// Returns the first `output_len` bytes of output for all the data fed so far.
+    pub fn finalize_to_vec(&self, output_len: usize) -> Vec<u8> {
+        let mut output = vec![0; output_len];
+        self.finalize_xof().fill(&mut output);
+        output
+    }
+}
+
+impl fmt::Debug for Xof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Xof").field("algorithm", &self.algorithm).finish()
+    }
+}
+
+impl io::Write for Xof {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum ReaderInner {
+    BLAKE3(blake3::OutputReader),
+    SHA3(Sha3XofReader),
+    Sponge(Sponge),
+}
+
+/// A reader over the output of an extendable-output hasher, returned by `Xof::finalize_xof`.
+///
+/// The output never ends: every read fills the whole buffer. The BLAKE3 output can also be
+/// seeked to any position with `io::Seek`; the SHAKE and cSHAKE outputs can only be read in
+/// order, and seeking them returns an `io::ErrorKind::Unsupported` error.
+pub struct XofReader {
+    inner: ReaderInner,
+}
+
+impl XofReader {
+    /// Fills the whole buffer with the next bytes of output.
+    pub fn fill(&mut self, output: &mut [u8]) {
+        match &mut self.inner {
+            ReaderInner::BLAKE3(reader) => reader.fill(output),
+            ReaderInner::SHA3(reader) => reader.read(output),
+            ReaderInner::Sponge(sponge) => sponge.squeeze(output),
+        }
+    }
+}
+
+impl fmt::Debug for XofReader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("XofReader").finish()
+    }
+}
+
+impl io::Read for XofReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill(buf);
+        Ok(buf.len())
+    }
+}
+
+impl io::Seek for XofReader {
+    fn seek(&mut self, position: io::SeekFrom) -> io::Result<u64> {
+        let reader = match &mut self.inner {
+            ReaderInner::BLAKE3(reader) => reader,
+            _ => return Err(io::Error::new(io::ErrorKind::Unsupported, "only the BLAKE3 output can be seeked")),
+        };
+        let target = match position {
+            io::SeekFrom::Start(offset) => Some(offset),
+            io::SeekFrom::Current(delta) => reader.position().checked_add_signed(delta),
+            io::SeekFrom::End(_) => {
+                return Err(io::Error::new(io::ErrorKind::Unsupported, "the output has no end"));
+            }
+        };
+        let target = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+        reader.set_position(target);
+        Ok(target)
+    }
+}
+
+/// Hashes the given bytes with the selected extendable-output function, returning
+/// `output_len` bytes of output.
+pub fn xof_bytes(xof_algorithm: XofAlgorithm, input: &[u8], output_len: usize) -> Vec<u8> {
+    let mut xof = Xof::new(xof_algorithm);
+    xof.update(input);
+    xof.finalize_to_vec(output_len)
+}
+
+/// Hashes the given string with the selected extendable-output function, returning
+/// `output_len` bytes of output.
+pub fn xof_string(xof_algorithm: XofAlgorithm, input: &str, output_len: usize) -> Vec<u8> {
+    xof_bytes(xof_algorithm, input.as_bytes(), output_len)
+}
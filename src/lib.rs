@@ -15,9 +15,33 @@
 //! 2. Levenshtein Distance [Wiki](https://en.wikipedia.org/wiki/Levenshtein_distance)
 //! 3. Jaro Winkler Distance [Wiki](https://en.wikipedia.org/wiki/Jaro-Winkler_distance)
 //!
+//! For near-duplicate detection at scale there are SimHash fingerprints (compared with a
+//! bitwise `hamming`) and MinHash signatures with an `LshIndex` to find candidate pairs.
+//!
 //! ## Hashing
 //! This crate provides both string and file based hashing.
 //!
+//! The hashes are returned as a `Digest`, which keeps the raw bytes along with the algorithm
+//! that produced them, and can be encoded as hex, base64 or base32.
+//! The self-describing Multihash, Subresource Integrity (`sha384-<base64>`) and `algo:hex`
+//! forms carry the algorithm with the value, and `verify_file` checks a file against any of them.
+//!
+//! With the `mmap` feature (enabled by default), large files hashed with BLAKE3 are memory
+//! mapped and hashed on multiple threads.
+//!
+//! `HashOptions` can also report the progress of hashing a file and cancel it, and
+//! `hash_file_with_stats` returns the bytes read, elapsed time and throughput with the digest.
+//!
+//! `hash_file_range` hashes a byte range of a file, and `hash_file_sampled` a few blocks and
+//! the size, for quick change detection on huge files. Sampled digests are not integrity
+//! guarantees, and are returned as a separate `SampledDigest` type.
+//!
+//! `HashingReader` and `HashingWriter` wrap any reader or writer and hash the data as it is
+//! copied, optionally checking it against an expected digest.
+//!
+//! With the `tokio` feature, `hash_async_reader` and `AsyncHashingWriter` hash async streams
+//! without blocking the runtime.
+//!
 //! The hashing (file & string) provided include:
 //! 1. MD5 (for compatibility, not recommended due to proven weaknesses).
 //! 2. SHA2 - 256
@@ -27,16 +51,67 @@
 //! 6. BLAKE 2s (256 bit)
 //! 7. BLAKE 2b (512 bit)
 //! 8. BLAKE 3 (256 bit)
+//! 9. SHA1 (for compatibility, not recommended due to proven weaknesses).
+//! 10. SHA2 - 224
+//! 11. SHA2 - 384
+//! 12. SHA2 - 512/256
+//! 13. SHA3 - 224
+//! 14. SHA3 - 384
+//! 15. Keccak - 256 (the original Keccak padding, as used by Ethereum)
+//! 16. RIPEMD - 160
+//! 17. Whirlpool (512 bit)
+//!
+//! Non-cryptographic hashes and checksums (XXH3, XXH64, CRC-32, CRC-32C, CRC-64, Adler-32,
+//! FNV-1a and MurmurHash3) have their own `ChecksumAlgorithm` and `Checksum` types, so they
+//! cannot be mistaken for a cryptographic hash. Use them for cache keys, deduplication and
+//! detecting accidental corruption, never where security matters.
+//!
+//! ## Checksum Manifests
+//! The `manifest` module writes, parses and verifies checksum manifests, both in the GNU
+//! coreutils format (`sha256sum`) and the BSD tag format (`sha256sum --tag`).
+//!
+//! ## Content-Defined Chunking
+//! The `chunking` module splits files and streams at content-defined boundaries with FastCDC
+//! or Buzhash, for deduplicated storage.
+//!
+//! ## Sketches
+//! The `sketch` module has probabilistic data structures for very large sets: Bloom and
+//! cuckoo filters for membership, a Count-Min sketch for frequencies and HyperLogLog for
+//! distinct counts. They can be merged across shards and serialized to a compact binary form.
+//!
+//! ## Sharding
+//! The `sharding` module assigns keys to nodes with a consistent hash ring, rendezvous
+//! hashing, jump consistent hash or a Maglev table, reports how many keys move when the nodes
+//! change, and places keys in stable percentage buckets for A/B tests.
+//!
+//! ## Password Hashing
+//! The `password` module hashes passwords with Argon2id, scrypt, PBKDF2-HMAC-SHA256/512 or
+//! bcrypt, with random salts and tunable costs. The hashes are stored as PHC strings, and
+//! `needs_rehash` tells when they should be upgraded to new parameters.
+//!
+//! ## Merkle Trees
+//! The `merkle` module builds RFC 6962 Merkle trees over any hash algorithm, from leaves or
+//! from the fixed-size blocks of a file. It produces inclusion proofs for single leaves and
+//! consistency proofs between tree sizes, which can be verified without the tree.
 //!
 //!
 pub mod strings;
 pub mod hash;
+pub mod manifest;
+pub mod chunking;
+pub mod sketch;
+pub mod sharding;
+pub mod password;
+pub mod merkle;
 
 #[cfg(test)]
 mod tests {
-    use super::*;
     use super::strings::*;
     use super::hash::*;
+    use super::sketch::*;
+    use super::sharding::{self, HashRing, JumpHash, Maglev, Rendezvous, Sharder};
+    use super::password::{self, PasswordError, PasswordParams};
+    use super::merkle::{self, MerkleTree};
 
     #[test]
     fn hamming_ascii_test() {
@@ -157,11 +232,11 @@ mod tests {
         let expected_hash_ex4 = "7fc56270e7a70fa81a5935b72eacbe29";
         let expected_hash_ex5 = "db1a8b8aedec0bd3b804b7a833f5cfe2";
 
-        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex1, true), expected_hash_ex1);
-        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex2, true), expected_hash_ex2);
-        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex3, true), expected_hash_ex3);
-        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex4, true), expected_hash_ex4);
-        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex5, true), expected_hash_ex5);
+        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::MD5, examples.ex5).to_hex(), expected_hash_ex5);
     }
 
     #[test]
@@ -173,11 +248,11 @@ mod tests {
         let expected_hash_ex4 = "559aead08264d5795d3909718cdd05abd49572e84fe55590eef31a88a08fdffd";
         let expected_hash_ex5 = "8d376648a83c999de09ff7638a9729fc050ff475f3470ea001914bd80f40694d";
 
-        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex1, true), expected_hash_ex1);
-        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex2, true), expected_hash_ex2);
-        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex3, true), expected_hash_ex3);
-        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex4, true), expected_hash_ex4);
-        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex5, true), expected_hash_ex5);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_256, examples.ex5).to_hex(), expected_hash_ex5);
     }
 
     #[test]
@@ -189,11 +264,11 @@ mod tests {
         let expected_hash_ex4 = "21b4f4bd9e64ed355c3eb676a28ebedaf6d8f17bdc365995b319097153044080516bd083bfcce66121a3072646994c8430cc382b8dc543e84880183bf856cff5";
         let expected_hash_ex5 = "89ab2bf39588b9500fc44f9932db127bd65e9c8eba68f91d28104a7d369cbdbc50f265515ec81bf8a9b222cb9a8687a644cf51bc81e4f67ed386772309a5f2b7";
 
-        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex1, true), expected_hash_ex1);
-        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex2, true), expected_hash_ex2);
-        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex3, true), expected_hash_ex3);
-        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex4, true), expected_hash_ex4);
-        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex5, true), expected_hash_ex5);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512, examples.ex5).to_hex(), expected_hash_ex5);
     }
 
     #[test]
@@ -205,11 +280,11 @@ mod tests {
         let expected_hash_ex4 = "1c9ebd6caf02840a5b2b7f0fc870ec1db154886ae9fe621b822b14fd0bf513d6";
         let expected_hash_ex5 = "452ccfd74f73a9d2a15355693f36cc1b0c1a605b6bd9b45908bdb2be34859d07";
 
-        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex1, true), expected_hash_ex1);
-        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex2, true), expected_hash_ex2);
-        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex3, true), expected_hash_ex3);
-        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex4, true), expected_hash_ex4);
-        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex5, true), expected_hash_ex5);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_256, examples.ex5).to_hex(), expected_hash_ex5);
     }
 
     #[test]
@@ -221,11 +296,11 @@ mod tests {
         let expected_hash_ex4 = "f5f0eaa9ca3fd0c4e0d72a3471e4b71edaabe2d01c4b25e16715004ed91e663a1750707cc9f04430f19b995f4aba21b0ec878fc5c4eb838a18df5bf9fdc949df";
         let expected_hash_ex5 = "a41c503a2f19925aa5c27ad7970b6144efe0e9cbe6858fc737353a0db8ad446402c1238c6c2aa70ae2751a64e862df3ebacb5683629a26575b664276a2f144a2";
 
-        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex1, true), expected_hash_ex1);
-        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex2, true), expected_hash_ex2);
-        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex3, true), expected_hash_ex3);
-        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex4, true), expected_hash_ex4);
-        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex5, true), expected_hash_ex5);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_512, examples.ex5).to_hex(), expected_hash_ex5);
     }
 
     #[test]
@@ -237,11 +312,11 @@ mod tests {
         let expected_hash_ex4 = "98e14bd264b8837ddf8fd12d6f5641d59c369720b02c105feaf99f1b6a7b9618";
         let expected_hash_ex5 = "154eaff13d999834a00cb8be3d2ed80e41cdfbe9ce907b193f58747c42a6a238";
 
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex1, true), expected_hash_ex1);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex2, true), expected_hash_ex2);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex3, true), expected_hash_ex3);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex4, true), expected_hash_ex4);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex5, true), expected_hash_ex5);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2S, examples.ex5).to_hex(), expected_hash_ex5);
     }
 
     #[test]
@@ -253,11 +328,11 @@ mod tests {
         let expected_hash_ex4 = "3e6173df7f81c1eb9ce997312fe72e441b40b72cd5ffca23d05ef805bf6e938e1ef9c3cac173005f77d698c2ca30dd785eb745aad32fcb4d5afff91c30ad7472";
         let expected_hash_ex5 = "97033cf9ca7c993c935beb9f230a400a6ee35fccc478ca55b1cc135aa77dfbd75fc0b93812f525368559f419302dce3b3d961ed4d77b7c19e84d50df072588ec";
 
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex1, true), expected_hash_ex1);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex2, true), expected_hash_ex2);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex3, true), expected_hash_ex3);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex4, true), expected_hash_ex4);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex5, true), expected_hash_ex5);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE2B, examples.ex5).to_hex(), expected_hash_ex5);
     }
 
     #[test]
@@ -269,10 +344,1221 @@ mod tests {
         let expected_hash_ex4 = "32684bfa28c0c84d6f210511aace0efc5171c7889148ba89208d5aa29705fa98";
         let expected_hash_ex5 = "31fe46b899e13ed5a11d1196797af433003c4b8495f194db60813ad871154fe6";
 
-        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex1, true), expected_hash_ex1);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex2, true), expected_hash_ex2);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex3, true), expected_hash_ex3);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex4, true), expected_hash_ex4);
-        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex5, true), expected_hash_ex5);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::BLAKE3, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_sha1_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "a15cc98b9ba24bec6468a8c1f0ffd31d8c7de67d";
+        let expected_hash_ex2 = "3cdf2936da2fc556bfa533ab1eb59ce710ac80e5";
+        let expected_hash_ex3 = "86f7e437faa5a7fce15d1ddcb9eaeaea377667b8";
+        let expected_hash_ex4 = "6dcd4ce23d88e2ee9568ba546c007c63d9131c1b";
+        let expected_hash_ex5 = "289bc78114fb1a5fada8e0a774402629fbec9391";
+
+        assert_eq!(hash_string(HashAlgorithm::SHA1, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA1, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA1, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA1, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA1, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_sha224_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "2bb3f26cff2fb42805047162173dc544bc7dbe70416604ba09ae2311";
+        let expected_hash_ex2 = "23fa1e672a6c2acdc4d7bfae713e0c9337ba057b5d5ace2685b59321";
+        let expected_hash_ex3 = "abd37534c7d9a2efb9465de931cd7055ffdb8879563ae98078d6d6d5";
+        let expected_hash_ex4 = "5cfe2cddbb9940fb4d8505e25ea77e763a0077693dbb01b1a6aa94f2";
+        let expected_hash_ex5 = "50fdcc09075b32d1f467f680ce0446770a53962dac87d8ccfa65bd89";
+
+        assert_eq!(hash_string(HashAlgorithm::SHA2_224, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_224, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_224, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_224, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_224, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_sha384_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "0410403f885dee2e0eb377f2e2f0e3728d32babbecb08a2d4e8ab5f3b38766f23ea349006ade1558acefc48ec18fd807";
+        let expected_hash_ex2 = "b1583f4b2e1bf53fc31e9dfb8e8d945a62955da709f280a9066aa8f31ef688d65e0e9816a5f1f11363b3898820bd1576";
+        let expected_hash_ex3 = "54a59b9f22b0b80880d8427e548b7c23abd873486e1f035dce9cd697e85175033caa88e6d57bc35efae0b5afd3145f31";
+        let expected_hash_ex4 = "ad14aaf25020bef2fd4e3eb5ec0c50272cdfd66074b0ed037c9a11254321aac0729985374beeaa5b80a504d048be1864";
+        let expected_hash_ex5 = "7147693078e8a3181ceeee9c73b8f90cc5d5617d9b2006876ae57316f9ce59dbb6dea0610b91822aab5d49b21ff7730d";
+
+        assert_eq!(hash_string(HashAlgorithm::SHA2_384, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_384, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_384, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_384, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_384, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_sha512_256_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "00a20823ea85d9d6a90ee56e0a015778b1fc37f97f28066bba7c80d34c5335d6";
+        let expected_hash_ex2 = "48710ecb0415f471b2d606f1058b88efeeb5c14c92c71b78614b1fcaacd1b1e4";
+        let expected_hash_ex3 = "455e518824bc0601f9fb858ff5c37d417d67c2f8e0df2babe4808858aea830f8";
+        let expected_hash_ex4 = "65a992ad19967492b5780d76a4733af553f796f688b79102d01ec7fde5590cab";
+        let expected_hash_ex5 = "e850bdbb00dc15d702cdd130bfa8d447e4adc3df753ecc44ad7d2487f3348b45";
+
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512_256, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512_256, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512_256, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512_256, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA2_512_256, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_sha3_224_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "70e2990241450fde7a49a74294c3d98f7e5cc222cde892993a42ea6e";
+        let expected_hash_ex2 = "3688446f1fcd46fdc47d7da4400caaba070a45e0a505ccc84e6a65a6";
+        let expected_hash_ex3 = "9e86ff69557ca95f405f081269685b38e3a819b309ee942f482b6a8b";
+        let expected_hash_ex4 = "97e2f98c0938943ab1a18a1721a04dff922ecc1ad14d4bbf905c02ca";
+        let expected_hash_ex5 = "b7f9ff2ed5ec204a81fe65e9af5aeb1224517ce423a94cbf376bc929";
+
+        assert_eq!(hash_string(HashAlgorithm::SHA3_224, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_224, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_224, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_224, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_224, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_sha3_384_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "302bd57795cea0cf94b1d5357cfc8f3bb568c660a255a52c3a587d8ce8ba30c4105e078641fb2802c315e73069a19463";
+        let expected_hash_ex2 = "becc971f35c03c516baf4725d2bbcdb79f85a78155442e0b9205fde564f9445152c592a5835219aa2459c4a6ae7dda1a";
+        let expected_hash_ex3 = "1815f774f320491b48569efec794d249eeb59aae46d22bf77dafe25c5edc28d7ea44f93ee1234aa88f61c91912a4ccd9";
+        let expected_hash_ex4 = "15000d20f59aa483b5eac0a1f33abe8e09dea1054d173d3e7443c68035b99240b50f7abdb9553baf220320384c6b1cd6";
+        let expected_hash_ex5 = "ac02ca79fe3c1237d8194725ebed789d72bcc562727a7cc2822abf01f49677052c8d0cd1c05f99e835dffd25df2bdd98";
+
+        assert_eq!(hash_string(HashAlgorithm::SHA3_384, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_384, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_384, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_384, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::SHA3_384, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_keccak256_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "625fa3b3069f2c46f44eb69d75e197abbd63548b376e1df282ea49cc18199bb6";
+        let expected_hash_ex2 = "b104e6a8e5e1477c7a8346486401cbd4f10ab4840a4201066d9b59b747cb6f88";
+        let expected_hash_ex3 = "3ac225168df54212a25c1c01fd35bebfea408fdac2e31ddd6f80a4bbf9a5f1cb";
+        let expected_hash_ex4 = "03783fac2efed8fbc9ad443e592ee30e61d65f471140c10ca155e937b435b760";
+        let expected_hash_ex5 = "d091cd3df05fb568423a46a7356399cb5762ca1e44596c205f15e1161e1c7ff9";
+
+        assert_eq!(hash_string(HashAlgorithm::KECCAK256, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::KECCAK256, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::KECCAK256, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::KECCAK256, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::KECCAK256, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_ripemd160_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "15228c5a1ad5371addf1b49e8f707cdcccc159dd";
+        let expected_hash_ex2 = "857b2f94d0b47a0a61bbee5993fd6ff34c0fe4a1";
+        let expected_hash_ex3 = "0bdc9d2d256b3ee9daae347be6f4dc835a467ffe";
+        let expected_hash_ex4 = "ddadef707ba62c166051b9e3cd0294c27515f2bc";
+        let expected_hash_ex5 = "aa5b56cd0b923f871b6d7470b31919af2a8ed2c2";
+
+        assert_eq!(hash_string(HashAlgorithm::RIPEMD160, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::RIPEMD160, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::RIPEMD160, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::RIPEMD160, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::RIPEMD160, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_whirlpool_hash_string() {
+        let examples = HashTestStrings::new();
+        let expected_hash_ex1 = "e4638f980f5120d3acfceb85a50012d0e89acc3ef1939243dabcca3457f2253c5767af75ee3fffa4aa449cc81ed200f49efacc7913b5d93ed45110021a5a353d";
+        let expected_hash_ex2 = "42858987414fe0c406d287f84292e90ad2c4458f9d5493be95780c4bc405dc506b9db976ddbde235d969bfdff2a6abf7c55d5bcf8a4138aab828409096d079fa";
+        let expected_hash_ex3 = "8aca2602792aec6f11a67206531fb7d7f0dff59413145e6973c45001d0087b42d11bc645413aeff63a42391a39145a591a92200d560195e53b478584fdae231a";
+        let expected_hash_ex4 = "3acb384b67995724469edc254c71f4ffcb699424606bf018254fad2e42ed613bd4a350453498a354a60dd8bd7c2ad141fb4611a7460a8f50841e569749dc32ca";
+        let expected_hash_ex5 = "491dad2898a75442ae49c085d5a5ae902061a54bca842991db11f5e868f5e973d8a1c57c71594f60a2883558001e1dfa5126e2541c2ba8a4eac7a02328aac4a7";
+
+        assert_eq!(hash_string(HashAlgorithm::WHIRLPOOL, examples.ex1).to_hex(), expected_hash_ex1);
+        assert_eq!(hash_string(HashAlgorithm::WHIRLPOOL, examples.ex2).to_hex(), expected_hash_ex2);
+        assert_eq!(hash_string(HashAlgorithm::WHIRLPOOL, examples.ex3).to_hex(), expected_hash_ex3);
+        assert_eq!(hash_string(HashAlgorithm::WHIRLPOOL, examples.ex4).to_hex(), expected_hash_ex4);
+        assert_eq!(hash_string(HashAlgorithm::WHIRLPOOL, examples.ex5).to_hex(), expected_hash_ex5);
+    }
+
+    #[test]
+    fn test_digest_encodings() {
+        let digest = hash_string(HashAlgorithm::MD5, "a");
+        assert_eq!(digest.algorithm(), HashAlgorithm::MD5);
+        assert_eq!(digest.len(), 16);
+        assert_eq!(digest.as_bytes()[..4], [0x0c, 0xc1, 0x75, 0xb9]);
+        assert_eq!(digest.to_hex(), "0cc175b9c0f1b6a831c399e269772661");
+        assert_eq!(digest.to_hex_upper(), "0CC175B9C0F1B6A831C399E269772661");
+        assert_eq!(digest.to_base64(), "DMF1ucDxtqgxw5niaXcmYQ==");
+        assert_eq!(digest.to_base32(), "BTAXLOOA6G3KQMODTHRGS5ZGME======");
+        assert_eq!(format!("{}", digest), digest.to_hex());
+        assert_eq!(format!("{:X}", digest), digest.to_hex_upper());
+
+        assert_eq!(Digest::from_hex(HashAlgorithm::MD5, "0CC175B9C0F1B6A831C399E269772661").unwrap(), digest);
+        assert_eq!(Digest::from_base64(HashAlgorithm::MD5, &digest.to_base64()).unwrap(), digest);
+        assert_eq!(Digest::from_base32(HashAlgorithm::MD5, &digest.to_base32()).unwrap(), digest);
+        assert_eq!(Digest::from_bytes(HashAlgorithm::MD5, digest.as_bytes()).unwrap(), digest);
+    }
+
+    #[test]
+    fn test_digest_parse_errors() {
+        assert_eq!(Digest::from_hex(HashAlgorithm::SHA2_256, "0cc175b9c0f1b6a831c399e269772661"),
+                   Err(DigestParseError::InvalidLength { expected: 32, actual: 16 }));
+        assert!(matches!(Digest::from_hex(HashAlgorithm::MD5, "not hex"), Err(DigestParseError::InvalidEncoding(_))));
+        assert!(matches!(Digest::from_base64(HashAlgorithm::MD5, "DMF1ucDxtqgxw5niaXcmYQ"), Err(DigestParseError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn test_digest_comparison() {
+        let digest_a = hash_string(HashAlgorithm::SHA2_256, "a");
+        let digest_b = hash_string(HashAlgorithm::SHA2_256, "b");
+        assert!(digest_a.ct_eq(&hash_bytes(HashAlgorithm::SHA2_256, b"a")));
+        assert!(!digest_a.ct_eq(&digest_b));
+        assert!(!digest_a.ct_eq(&hash_string(HashAlgorithm::SHA3_256, "a")));
+        assert_ne!(digest_a, digest_b);
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(digest_a.clone());
+        assert!(set.contains(&digest_a));
+        assert!(!set.contains(&digest_b));
+    }
+
+    const ALL_ALGORITHMS: [HashAlgorithm; 17] = [
+        HashAlgorithm::MD5,
+        HashAlgorithm::SHA2_256,
+        HashAlgorithm::SHA2_512,
+        HashAlgorithm::SHA3_256,
+        HashAlgorithm::SHA3_512,
+        HashAlgorithm::BLAKE2S,
+        HashAlgorithm::BLAKE2B,
+        HashAlgorithm::BLAKE3,
+        HashAlgorithm::SHA1,
+        HashAlgorithm::SHA2_224,
+        HashAlgorithm::SHA2_384,
+        HashAlgorithm::SHA2_512_256,
+        HashAlgorithm::SHA3_224,
+        HashAlgorithm::SHA3_384,
+        HashAlgorithm::KECCAK256,
+        HashAlgorithm::RIPEMD160,
+        HashAlgorithm::WHIRLPOOL,
+    ];
+
+    #[test]
+    fn test_incremental_hasher() {
+        let examples = HashTestStrings::new();
+        for &algorithm in ALL_ALGORITHMS.iter() {
+            let mut hasher = Hasher::new(algorithm);
+            for chunk in examples.ex5.as_bytes().chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), hash_string(algorithm, examples.ex5));
+
+            hasher.reset();
+            assert_eq!(hasher.finalize(), hash_string(algorithm, ""));
+
+            std::io::Write::write_all(&mut hasher, examples.ex1.as_bytes()).unwrap();
+            assert_eq!(hasher.finalize(), hash_string(algorithm, examples.ex1));
+            assert_eq!(hasher.algorithm(), algorithm);
+        }
+    }
+
+    #[test]
+    fn test_manifest_parsing() {
+        use super::manifest::{self, ManifestFormat};
+
+        let hex = "0cc175b9c0f1b6a831c399e269772661";
+        let input = format!("{}  plain.txt\n{} *binary.bin\n\\{}  back\\\\slash\\nnewline\n\nMD5 (tagged (1).txt) = {}\n",
+                            hex, hex, hex, hex);
+        let entries = manifest::parse(&input, HashAlgorithm::MD5).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].path.to_str(), Some("plain.txt"));
+        assert!(!entries[0].binary);
+        assert!(entries[1].binary);
+        assert_eq!(entries[2].path.to_str(), Some("back\\slash\nnewline"));
+        assert_eq!(entries[3].path.to_str(), Some("tagged (1).txt"));
+        assert_eq!(entries[3].digest.to_hex(), hex);
+
+        let rendered = manifest::render(&entries, ManifestFormat::Gnu);
+        assert_eq!(rendered.lines().nth(2), Some(format!("\\{}  back\\\\slash\\nnewline", hex).as_str()));
+        assert_eq!(manifest::parse(&rendered, HashAlgorithm::MD5).unwrap(), entries);
+
+        let error = manifest::parse("0cc175b9  short.txt\n", HashAlgorithm::MD5).unwrap_err();
+        assert_eq!(error.line, 1);
+        assert!(manifest::parse("FOO (a.txt) = 00\n", HashAlgorithm::MD5).is_err());
+        assert!(manifest::parse(&format!("{}\n", hex), HashAlgorithm::MD5).is_err());
+    }
+
+    #[test]
+    fn test_hash_files_worker_panic() {
+        let hash = |path: &std::path::Path, _: &HashOptions| {
+            if path.ends_with("panic") {
+                panic!("hashing {} failed", path.display());
+            }
+            Ok(hash_string(HashAlgorithm::MD5, &path.to_string_lossy()))
+        };
+        let paths = ["a", "panic", "b", "c", "panic", "d"];
+        for &ordered in [true, false].iter() {
+            let options = BatchOptions::new().threads(2).ordered(ordered);
+            let mut results: Vec<_> = hash_files_with(&paths, &options, hash).collect();
+            if !ordered {
+                results.sort_by(|a, b| a.0.cmp(&b.0));
+            }
+            assert_eq!(results.len(), paths.len());
+            for (path, result) in results {
+                match result {
+                    Ok(digest) => assert_eq!(digest, hash_string(HashAlgorithm::MD5, path.to_str().unwrap())),
+                    Err(e) => {
+                        assert!(path.ends_with("panic"));
+                        assert_eq!(e.to_string(), "worker panicked");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hmac_rfc4231() {
+        let long_key = [0xaa; 131];
+        let cases: [(&[u8], &[u8], &str, &str); 6] = [
+            (&[0x0b; 20], b"Hi There",
+             "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+             "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"),
+            (b"Jefe", b"what do ya want for nothing?",
+             "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+             "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"),
+            (&[0xaa; 20], &[0xdd; 50],
+             "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+             "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb"),
+            (&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25], &[0xcd; 50],
+             "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+             "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3dba91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd"),
+            (&long_key, b"Test Using Larger Than Block-Size Key - Hash Key First",
+             "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+             "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"),
+            (&long_key, b"This is a test using a larger than block-size key and a larger than block-size data. \
+            The key needs to be hashed before being used by the HMAC algorithm.",
+             "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+             "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58"),
+        ];
+        for (key, data, sha256, sha512) in cases.iter() {
+            assert_eq!(hmac_bytes(HashAlgorithm::SHA2_256, key, data).unwrap().to_hex(), *sha256);
+            assert_eq!(hmac_bytes(HashAlgorithm::SHA2_512, key, data).unwrap().to_hex(), *sha512);
+        }
+    }
+
+    #[test]
+    fn test_hmac_rfc2202() {
+        let cases: [(&[u8], &[u8], &str); 6] = [
+            (&[0x0b; 16], b"Hi There", "9294727a3638bb1c13f48ef8158bfc9d"),
+            (b"Jefe", b"what do ya want for nothing?", "750c783e6ab0b503eaa86e310a5db738"),
+            (&[0xaa; 16], &[0xdd; 50], "56be34521d144c88dbb8c733f0e8b3f6"),
+            (&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25], &[0xcd; 50],
+             "697eaf0aca3a3aea3a75164746ffaa79"),
+            (&[0xaa; 80], b"Test Using Larger Than Block-Size Key - Hash Key First", "6b1ab7fe4bd7bf8f0b62e6ce61b9d0cd"),
+            (&[0xaa; 80], b"Test Using Larger Than Block-Size Key and Larger Than One Block-Size Data",
+             "6f630fad67cda0ee1fb1f562db3aa53e"),
+        ];
+        for (key, data, md5) in cases.iter() {
+            assert_eq!(hmac_bytes(HashAlgorithm::MD5, key, data).unwrap().to_hex(), *md5);
+        }
+
+        let sha1_cases: [(&[u8], &[u8], &str); 6] = [
+            (&[0x0b; 20], b"Hi There", "b617318655057264e28bc0b6fb378c8ef146be00"),
+            (b"Jefe", b"what do ya want for nothing?", "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"),
+            (&[0xaa; 20], &[0xdd; 50], "125d7342b9ac11cd91a39af48aa17b4f63f175d3"),
+            (&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25], &[0xcd; 50],
+             "4c9007f4026250c6bc8414f9bf50c86c2d7235da"),
+            (&[0xaa; 80], b"Test Using Larger Than Block-Size Key - Hash Key First", "aa4ae5e15272d00e95705637ce8a3b55ed402112"),
+            (&[0xaa; 80], b"Test Using Larger Than Block-Size Key and Larger Than One Block-Size Data",
+             "e8e99d0f45237d786d6bbaa7965c7808bbff1a91"),
+        ];
+        for (key, data, sha1) in sha1_cases.iter() {
+            assert_eq!(hmac_bytes(HashAlgorithm::SHA1, key, data).unwrap().to_hex(), *sha1);
+        }
+        assert_eq!(hmac_string(HashAlgorithm::WHIRLPOOL, b"key", "The quick brown fox jumps over the lazy dog").unwrap().to_hex(),
+                   "7f7192e3a155cb6a8171584ba146882f26821658112dfd2601272db013517a31\
+                    e573637d146584596f86a884eb0decc9514dde000ecf2476dc5d436a92197527");
+        assert!(hmac_bytes(HashAlgorithm::BLAKE3, b"key", b"data").is_err());
+    }
+
+    #[test]
+    fn test_hmac_streaming_and_verify() {
+        let input = "The quick brown fox jumps over the lazy dog";
+        assert_eq!(hmac_string(HashAlgorithm::SHA3_256, b"key", input).unwrap().to_hex(),
+                   "8c6e0683409427f8931711b10ca92a506eb1fafa48fadd66d76126f47ac2c333");
+        assert_eq!(hmac_string(HashAlgorithm::SHA3_512, b"key", input).unwrap().to_hex(),
+                   "237a35049c40b3ef5ddd960b3dc893d8284953b9a4756611b1b61bffcf53edd979f93547db714b06ef0a692062c609b70208ab8d4a280ceee40ed8100f293063");
+
+        let mut hmac = Hmac::new(HashAlgorithm::SHA2_256, b"key").unwrap();
+        for chunk in input.as_bytes().chunks(5) {
+            hmac.update(chunk);
+        }
+        let tag = hmac.finalize();
+        assert_eq!(tag, hmac_string(HashAlgorithm::SHA2_256, b"key", input).unwrap());
+        assert!(hmac.verify(tag.as_bytes()));
+        assert!(!hmac.verify(&tag.as_bytes()[..16]));
+        assert!(hmac_verify(HashAlgorithm::SHA2_256, b"key", input.as_bytes(), tag.as_bytes()).unwrap());
+        assert!(!hmac_verify(HashAlgorithm::SHA2_256, b"other key", input.as_bytes(), tag.as_bytes()).unwrap());
+
+        hmac.reset();
+        assert_eq!(hmac.finalize(), hmac_bytes(HashAlgorithm::SHA2_256, b"key", b"").unwrap());
+
+        assert_eq!(hmac_string(HashAlgorithm::BLAKE3, b"key", input), Err(UnsupportedAlgorithm(HashAlgorithm::BLAKE3)));
+        assert!(Hmac::new(HashAlgorithm::BLAKE2B, b"key").is_err());
+    }
+
+    #[test]
+    fn test_keyed_blake2() {
+        let input = "The quick brown fox jumps over the lazy dog";
+        assert_eq!(keyed_hash_string(HashAlgorithm::BLAKE2B, b"key", input).unwrap().to_hex(),
+                   "66f642208454bf2e066dac9eab68fae0146bb544c1d46e1f427008f068a45d872cd0c1fc23e7ba82a95d084aadf5e4af9edaf761fb6ced9e485a28c59a3f714c");
+        assert_eq!(keyed_hash_string(HashAlgorithm::BLAKE2S, b"key", input).unwrap().to_hex(),
+                   "eec94d00b8c9d214636adfad587bc9c75f271d7a64d9639ef2e959f94da468e6");
+        assert_eq!(keyed_hash_bytes(HashAlgorithm::BLAKE2B, b"secret", b"").unwrap().to_hex(),
+                   "865aca2ba0b9b941352e4680e14f543d1af37f7a3479304262a5da8c97468d9fe22636bae941d9c7b83b93efc36e82177606c72a1c00af48bb182c69d1f1abc3");
+        assert_eq!(keyed_hash_bytes(HashAlgorithm::BLAKE2B, b"", input.as_bytes()).unwrap(),
+                   hash_string(HashAlgorithm::BLAKE2B, input));
+
+        let params = Blake2Params::new().key(b"secret").salt(b"tenant-42").personal(b"tokens");
+        let mut hasher = KeyedHasher::with_blake2_params(HashAlgorithm::BLAKE2B, &params).unwrap();
+        hasher.update(b"pay");
+        hasher.update(b"load");
+        assert_eq!(hasher.finalize().to_hex(),
+                   "38b2c5a65b55e7aec84ca298691cd16430915374ee982e7db03dfae060a6be27ad8b96c6e53f1bec69910f74a99c1fe2711dca596d5f20158a9a24a4d42f6e05");
+        let params = Blake2Params::new().key(b"secret").salt(b"tenant").personal(b"tokens");
+        let mut hasher = KeyedHasher::with_blake2_params(HashAlgorithm::BLAKE2S, &params).unwrap();
+        hasher.update(b"payload");
+        let tag = hasher.finalize();
+        assert_eq!(tag.to_hex(), "e8b9504b183938a20c46d024f8a0de15886aec3d31fea1fe2493d818db71bf88");
+        assert!(hasher.verify(tag.as_bytes()));
+        hasher.reset();
+        assert!(!hasher.verify(tag.as_bytes()));
+
+        assert_eq!(KeyedHasher::with_blake2_params(HashAlgorithm::BLAKE2S, &Blake2Params::new().salt(b"tenant-42")).unwrap_err(),
+                   KeyedHashError::InvalidSaltLength { max: 8, actual: 9 });
+        assert_eq!(keyed_hash_bytes(HashAlgorithm::BLAKE2S, &[0; 33], b"").unwrap_err(),
+                   KeyedHashError::InvalidKeyLength { max: 32, actual: 33 });
+        assert_eq!(keyed_hash_bytes(HashAlgorithm::SHA2_256, b"key", b"").unwrap_err(),
+                   KeyedHashError::UnsupportedAlgorithm(HashAlgorithm::SHA2_256));
+    }
+
+    #[test]
+    fn test_keyed_blake3_and_derive_key() {
+        let key = [7u8; 32];
+        let tag = keyed_hash_string(HashAlgorithm::BLAKE3, &key, "payload").unwrap();
+        assert_eq!(tag.len(), 32);
+        assert_ne!(tag, hash_string(HashAlgorithm::BLAKE3, "payload"));
+        assert_ne!(tag, keyed_hash_string(HashAlgorithm::BLAKE3, &[8u8; 32], "payload").unwrap());
+        assert!(keyed_verify(HashAlgorithm::BLAKE3, &key, b"payload", tag.as_bytes()).unwrap());
+        assert!(!keyed_verify(HashAlgorithm::BLAKE3, &key, b"payloaf", tag.as_bytes()).unwrap());
+        assert_eq!(keyed_hash_bytes(HashAlgorithm::BLAKE3, b"short", b"").unwrap_err(),
+                   KeyedHashError::WrongKeyLength { expected: 32, actual: 5 });
+        let error = keyed_hash_bytes(HashAlgorithm::BLAKE3, &[0; 40], b"").unwrap_err();
+        assert_eq!(error, KeyedHashError::WrongKeyLength { expected: 32, actual: 40 });
+        assert_eq!(error.to_string(), "invalid key length 40 (expected 32)");
+
+        let context = "estahr 2020-03-01 tenant subkeys v1";
+        let mut tenant_a = [0u8; 32];
+        let mut tenant_b = [0u8; 32];
+        let mut long_key = [0u8; 100];
+        derive_key(context, b"tenant-a", &mut tenant_a);
+        derive_key(context, b"tenant-b", &mut tenant_b);
+        derive_key(context, b"tenant-a", &mut long_key);
+        assert_ne!(tenant_a, tenant_b);
+        assert_eq!(tenant_a[..], long_key[..32]);
+
+        let mut kdf = KeyedHasher::new_derive_key(context);
+        kdf.update(b"tenant-a");
+        assert_eq!(kdf.finalize().as_bytes(), &tenant_a[..]);
+    }
+
+    #[test]
+    fn test_blake3_keyed_test_vectors() {
+        // The keyed_hash and derive_key cases of the official BLAKE3 test_vectors.json,
+        // truncated to 32 bytes. The input is the bytes 0, 1, ..., 250, 0, 1, ...
+        let key = b"whats the Elvish word for friend";
+        let context = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+        let cases = [
+            (0, "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26",
+             "2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d"),
+            (1, "6d7878dfff2f485635d39013278ae14f1454b8c0a3a2d34bc1ab38228a80c95b",
+             "b3e2e340a117a499c6cf2398a19ee0d29cca2bb7404c73063382693bf66cb06c"),
+            (1023, "c951ecdf03288d0fcc96ee3413563d8a6d3589547f2c2fb36d9786470f1b9d6e",
+             "74a16c1c3d44368a86e1ca6df64be6a2f64cce8f09220787450722d85725dea5"),
+            (1025, "357dc55de0c7e382c900fd6e320acc04146be01db6a8ce7210b7189bd664ea69",
+             "effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb"),
+            (2049, "9f29700902f7c86e514ddc4df1e3049f258b2472b6dd5267f61bf13983b78dd5",
+             "2ea477c5515cc3dd606512ee72bb3e0e758cfae7232826f35fb98ca1bcbdf273"),
+            (8193, "954a2a75420c8d6547e3ba5b98d963e6fa6491addc8c023189cc519821b4a1f5",
+             "af1e0346e389b17c23200270a64aa4e1ead98c61695d917de7d5b00491c9b0f1"),
+        ];
+        for (len, keyed, derived) in cases.iter() {
+            let input: Vec<u8> = (0..*len).map(|i| (i % 251) as u8).collect();
+            assert_eq!(keyed_hash_bytes(HashAlgorithm::BLAKE3, key, &input).unwrap().to_hex(), *keyed);
+            let mut output = [0u8; 32];
+            derive_key(context, &input, &mut output);
+            assert_eq!(hex::encode(output), *derived);
+            let mut kdf = KeyedHasher::new_derive_key(context);
+            kdf.update(&input);
+            assert_eq!(kdf.finalize().to_hex(), *derived);
+        }
+    }
+
+    #[test]
+    fn test_shake_xof() {
+        assert_eq!(hex::encode(xof_string(XofAlgorithm::SHAKE128, "", 32)),
+                   "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26");
+        assert_eq!(hex::encode(xof_string(XofAlgorithm::SHAKE256, "", 64)),
+                   "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be");
+        assert_eq!(hex::encode(xof_bytes(XofAlgorithm::SHAKE128, b"abc", 40)),
+                   "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc844c50af32acd3f2c");
+        assert_eq!(hex::encode(xof_bytes(XofAlgorithm::SHAKE256, b"abc", 80)),
+                   "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa\
+                   9f8726e462a12a4feb06bd8801e751e41385141204f329979fd3047a13c56577");
+        assert_eq!(xof_bytes(XofAlgorithm::SHAKE128, b"abc", 16)[..], xof_bytes(XofAlgorithm::SHAKE128, b"abc", 500)[..16]);
+    }
+
+    #[test]
+    fn test_cshake_xof() {
+        let data = [0u8, 1, 2, 3];
+        let mut xof = Xof::cshake128(b"", b"Email Signature");
+        xof.update(&data);
+        assert_eq!(hex::encode(xof.finalize_to_vec(32)), "c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5");
+        let mut xof = Xof::cshake256(b"", b"Email Signature");
+        xof.update(&data);
+        assert_eq!(hex::encode(xof.finalize_to_vec(64)),
+                   "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd164020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c");
+
+        let data: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let mut xof = Xof::cshake128(b"", b"Email Signature");
+        for chunk in data.chunks(13) {
+            xof.update(chunk);
+        }
+        assert_eq!(hex::encode(xof.finalize_to_vec(32)), "c5221d50e4f822d96a2e8881a961420f294b7b24fe3d2094baed2c6524cc166b");
+
+        let mut xof = Xof::cshake256(b"estahr", b"fixtures");
+        xof.update(b"abc");
+        assert_eq!(hex::encode(xof.finalize_to_vec(40)), "51c3339991ae701b33cd94fb7b7419e705d2e15dfd4ab3f8ff3cdba6826c26d09b719b464b7b01b3");
+        let mut plain = Xof::cshake128(b"", b"");
+        plain.update(b"abc");
+        assert_eq!(plain.finalize_to_vec(40), xof_bytes(XofAlgorithm::SHAKE128, b"abc", 40));
+    }
+
+    #[test]
+    fn test_blake3_xof_reader() {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let long_output = xof_string(XofAlgorithm::BLAKE3, "abc", 1000);
+        assert_eq!(long_output[..32], hash_string(HashAlgorithm::BLAKE3, "abc").as_bytes()[..]);
+
+        let mut xof = Xof::new(XofAlgorithm::BLAKE3);
+        xof.update(b"abc");
+        let mut reader = xof.finalize_xof();
+        let mut chunk = [0u8; 100];
+        reader.seek(SeekFrom::Start(700)).unwrap();
+        reader.read_exact(&mut chunk).unwrap();
+        assert_eq!(chunk[..], long_output[700..800]);
+        assert_eq!(reader.seek(SeekFrom::Current(-500)).unwrap(), 300);
+        reader.read_exact(&mut chunk).unwrap();
+        assert_eq!(chunk[..], long_output[300..400]);
+        assert!(reader.seek(SeekFrom::End(0)).is_err());
+
+        let mut shake = Xof::new(XofAlgorithm::SHAKE256).finalize_xof();
+        assert_eq!(shake.seek(SeekFrom::Start(1)).unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    }
+
+    const ALL_CHECKSUMS: [ChecksumAlgorithm; 11] = [
+        ChecksumAlgorithm::XXH3_64,
+        ChecksumAlgorithm::XXH3_128,
+        ChecksumAlgorithm::XXH64,
+        ChecksumAlgorithm::CRC32,
+        ChecksumAlgorithm::CRC32C,
+        ChecksumAlgorithm::CRC64,
+        ChecksumAlgorithm::ADLER32,
+        ChecksumAlgorithm::FNV1A32,
+        ChecksumAlgorithm::FNV1A64,
+        ChecksumAlgorithm::MURMUR3_32,
+        ChecksumAlgorithm::MURMUR3_128,
+    ];
+
+    #[test]
+    fn test_checksum_string() {
+        // The "check" values of the CRC catalogue, and the reference xxHash values.
+        let check_cases = [
+            (ChecksumAlgorithm::CRC32, "cbf43926"),
+            (ChecksumAlgorithm::CRC32C, "e3069283"),
+            (ChecksumAlgorithm::CRC64, "995dc9bbdf1939fa"),
+            (ChecksumAlgorithm::ADLER32, "091e01de"),
+            (ChecksumAlgorithm::XXH64, "8cb841db40e6ae83"),
+        ];
+        for &(algorithm, expected) in check_cases.iter() {
+            assert_eq!(checksum_string(algorithm, "123456789").to_hex(), expected);
+        }
+        assert_eq!(checksum_string(ChecksumAlgorithm::XXH64, "").value(), 0xef46db3751d8e999);
+        assert_eq!(checksum_string(ChecksumAlgorithm::XXH3_64, "").value(), 0x2d06800538d394c2);
+        assert_eq!(checksum_string(ChecksumAlgorithm::XXH3_128, "").value(), 0x99aa06d3014798d86001c324468d497f);
+        assert_eq!(checksum_string(ChecksumAlgorithm::FNV1A32, "").value(), 0x811c9dc5);
+        assert_eq!(checksum_string(ChecksumAlgorithm::FNV1A64, "").value(), 0xcbf29ce484222325);
+        assert_eq!(checksum_string(ChecksumAlgorithm::MURMUR3_32, "").value(), 0);
+        assert_eq!(checksum_string(ChecksumAlgorithm::MURMUR3_32, "hello").value(), 0x248bfa47);
+        assert_eq!(checksum_string(ChecksumAlgorithm::MURMUR3_128, "hello").value(), 0xcbd8a7b341bd9b025b1e906a48ae1d19);
+
+        let examples = HashTestStrings::new();
+        let cases = [
+            (examples.ex1, ["84389c87", "34684531767c4367", "8752cdd4", "09db86c7b3d13f082e84ddb94fe06935"]),
+            (examples.ex2, ["210c8933", "af63994c86017ab3", "1ae0d785", "005d5203d63e335a89dc40059c1cc8c5"]),
+            (examples.ex3, ["e40c292c", "af63dc4c8601ec8c", "3c2569b2", "85555565f6597889e6b53a48510e895a"]),
+            (examples.ex4, ["c40bf6cc", "af63fc4c860222ec", "54dcf7ce", "035fc2b79a29b17a387df29c46dd9937"]),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(checksum_string(ChecksumAlgorithm::FNV1A32, input).to_hex(), expected[0]);
+            assert_eq!(checksum_string(ChecksumAlgorithm::FNV1A64, input).to_hex(), expected[1]);
+            assert_eq!(checksum_string(ChecksumAlgorithm::MURMUR3_32, input).to_hex(), expected[2]);
+            assert_eq!(checksum_string(ChecksumAlgorithm::MURMUR3_128, input).to_hex(), expected[3]);
+        }
+
+        let checksum = checksum_string(ChecksumAlgorithm::ADLER32, "a");
+        assert_eq!(checksum.to_be_bytes(), vec![0x00, 0x62, 0x00, 0x62]);
+        assert_eq!(format!("{}", checksum), "00620062");
+        assert_eq!(checksum.algorithm(), ChecksumAlgorithm::ADLER32);
+    }
+
+    #[test]
+    fn test_incremental_checksummer() {
+        let examples = HashTestStrings::new();
+        for &algorithm in ALL_CHECKSUMS.iter() {
+            let expected = checksum_string(algorithm, examples.ex5);
+            for &chunk_size in [1, 3, 7, 16, 17].iter() {
+                let mut checksummer = Checksummer::new(algorithm);
+                for chunk in examples.ex5.as_bytes().chunks(chunk_size) {
+                    checksummer.update(chunk);
+                }
+                assert_eq!(checksummer.finalize(), expected);
+            }
+
+            let mut checksummer = Checksummer::new(algorithm);
+            checksummer.update(examples.ex1.as_bytes());
+            checksummer.reset();
+            std::io::Write::write_all(&mut checksummer, examples.ex4.as_bytes()).unwrap();
+            assert_eq!(checksummer.finalize(), checksum_string(algorithm, examples.ex4));
+            assert_eq!(checksummer.algorithm(), algorithm);
+        }
+    }
+
+    #[test]
+    fn test_algorithm_metadata() {
+        assert_eq!(HashAlgorithm::all(), &ALL_ALGORITHMS[..]);
+        for &algorithm in HashAlgorithm::all() {
+            assert_eq!(algorithm.to_string().parse::<HashAlgorithm>(), Ok(algorithm));
+            assert_eq!(format!("{:?}", algorithm).parse::<HashAlgorithm>(), Ok(algorithm));
+            assert_eq!(hash_string(algorithm, "").len(), algorithm.output_len());
+            if let Some(name) = algorithm.iana_name() {
+                assert_eq!(name.parse::<HashAlgorithm>(), Ok(algorithm));
+            }
+            if let Some(code) = algorithm.multicodec() {
+                assert_eq!(HashAlgorithm::from_multicodec(code), Some(algorithm));
+            }
+        }
+
+        for spelling in ["sha256", "SHA2-256", "sha-256", "SHA_256", "sha2_256"].iter() {
+            assert_eq!(spelling.parse::<HashAlgorithm>(), Ok(HashAlgorithm::SHA2_256));
+        }
+        assert_eq!("blake3".parse::<HashAlgorithm>(), Ok(HashAlgorithm::BLAKE3));
+        assert_eq!("SHA3-384".parse::<HashAlgorithm>(), Ok(HashAlgorithm::SHA3_384));
+        assert_eq!("SHA-512/256".parse::<HashAlgorithm>(), Ok(HashAlgorithm::SHA2_512_256));
+        assert_eq!("BLAKE2b".parse::<HashAlgorithm>(), Ok(HashAlgorithm::BLAKE2B));
+        assert_eq!("sha-1".parse::<HashAlgorithm>(), Ok(HashAlgorithm::SHA1));
+        let error = "sha4".parse::<HashAlgorithm>().unwrap_err();
+        assert_eq!(error, ParseAlgorithmError("sha4".to_string()));
+        assert_eq!(error.to_string(), "unknown hash algorithm: \"sha4\"");
+
+        assert_eq!(HashAlgorithm::SHA2_256.to_string(), "sha2-256");
+        assert_eq!(HashAlgorithm::SHA2_256.multicodec(), Some(0x12));
+        assert_eq!(HashAlgorithm::SHA2_256.iana_name(), Some("sha-256"));
+        assert_eq!(HashAlgorithm::WHIRLPOOL.multicodec(), None);
+        assert_eq!(HashAlgorithm::from_multicodec(0x00), None);
+        assert_eq!(HashAlgorithm::SHA2_512.block_size(), 128);
+        assert_eq!(HashAlgorithm::SHA3_256.block_size(), 136);
+        assert!(HashAlgorithm::MD5.is_broken());
+        assert!(HashAlgorithm::SHA1.is_broken());
+        assert!(!HashAlgorithm::SHA2_256.is_broken());
+    }
+
+    #[test]
+    fn test_self_describing_digests() {
+        let digest = hash_string(HashAlgorithm::SHA2_256, "hello world");
+        let multihash = "1220b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+        assert_eq!(hex::encode(digest.to_multihash().unwrap()), multihash);
+        assert_eq!(Digest::from_multihash(&hex::decode(multihash).unwrap()), Ok(digest.clone()));
+        assert_eq!(Digest::parse(multihash), Ok(digest.clone()));
+
+        // Two byte varint code.
+        let ripemd = hash_string(HashAlgorithm::RIPEMD160, "abc");
+        let multihash = ripemd.to_multihash().unwrap();
+        assert_eq!(hex::encode(&multihash), "d320148eb208f7e05d987a9b044a8e98c6b087f15a0bfc");
+        assert_eq!(Digest::from_multihash(&multihash), Ok(ripemd));
+
+        assert!(hash_string(HashAlgorithm::WHIRLPOOL, "").to_multihash().is_err());
+        assert_eq!(Digest::from_multihash(&[0x00, 0x00]),
+                   Err(DigestParseError::UnknownAlgorithm("multicodec 0x0".to_string())));
+        assert!(matches!(Digest::from_multihash(&[0x12, 0x20, 0xb9]), Err(DigestParseError::InvalidEncoding(_))));
+        assert!(matches!(Digest::from_multihash(&[0x92]), Err(DigestParseError::InvalidEncoding(_))));
+
+        let script = hash_string(HashAlgorithm::SHA2_384, "alert('Hello, world.');");
+        let sri = "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO";
+        assert_eq!(script.to_sri().unwrap(), sri);
+        assert_eq!(Digest::from_sri(sri), Ok(script.clone()));
+        assert_eq!(Digest::from_sri(&format!("{}?ct=application/javascript", sri)), Ok(script.clone()));
+        assert_eq!(Digest::parse(sri), Ok(script));
+        assert!(hash_string(HashAlgorithm::SHA3_256, "").to_sri().is_err());
+        assert_eq!(Digest::from_sri("md5-1B2M2Y8AsgTpgAmY7PhCfg=="),
+                   Err(DigestParseError::UnknownAlgorithm("md5".to_string())));
+
+        let prefixed = digest.to_prefixed_hex();
+        assert_eq!(prefixed, "sha2-256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+        assert_eq!(Digest::from_prefixed_hex(&prefixed), Ok(digest.clone()));
+        assert_eq!(Digest::parse("SHA256:B94D27B9934D3E08A52E52D7DA7DABFAC484EFE37A5380EE9088F7ACE2EFCDE9"), Ok(digest));
+        assert!(matches!(Digest::parse("sha4:00"), Err(DigestParseError::UnknownAlgorithm(_))));
+        assert!(matches!(Digest::parse("sha256:00"), Err(DigestParseError::InvalidLength { .. })));
+    }
+
+    #[test]
+    fn test_simhash() {
+        let description = "Stainless steel insulated water bottle, 750 ml, keeps drinks cold for 24 hours \
+                           and hot for 12 hours, leak proof lid, BPA free";
+        let near_duplicate = "Stainless steel insulated water bottle, 750ml, keeps drinks cold for 24 hours \
+                              and hot for 12 hours, leak-proof lid, BPA free";
+        let unrelated = "Wireless over-ear headphones with active noise cancelling, 30 hour battery life \
+                         and fast charging over USB-C";
+
+        for &shingling in [Shingling::Chars(4), Shingling::Words(2)].iter() {
+            let a = simhash64(description, shingling);
+            assert_eq!(a, simhash64(description, shingling));
+            assert!(hamming(a, simhash64(near_duplicate, shingling)) < hamming(a, simhash64(unrelated, shingling)));
+
+            let a = simhash128(description, shingling);
+            assert!(hamming(a, simhash128(near_duplicate, shingling)) < hamming(a, simhash128(unrelated, shingling)));
+        }
+        assert!(hamming(simhash64(description, Shingling::Chars(4)), simhash64(near_duplicate, Shingling::Chars(4))) <= 10);
+
+        assert_eq!(simhash64("", Shingling::Chars(3)), 0);
+        assert_eq!(simhash64("ab", Shingling::Chars(3)), simhash64("ab", Shingling::Chars(5)));
+        assert_eq!(hamming(0u64, u64::MAX), 64);
+        assert_eq!(hamming(0u128, u128::MAX), 128);
+        assert_eq!(hamming(0b1011u64, 0b0110u64), 3);
+    }
+
+    #[test]
+    fn test_minhash_and_lsh() {
+        let minhasher = MinHasher::new(256, Shingling::Words(1));
+        assert_eq!(minhasher.num_hashes(), 256);
+
+        // The word sets share 60 of 100 words, a Jaccard similarity of 0.6.
+        let words: Vec<String> = (0..100).map(|i| format!("word{}", i)).collect();
+        let text_a = words[..80].join(" ");
+        let text_b = words[20..].join(" ");
+        let a = minhasher.signature(&text_a);
+        let b = minhasher.signature(&text_b);
+        assert_eq!(a.as_slice().len(), 256);
+        assert!((a.jaccard(&b) - 0.6).abs() < 0.1, "estimate {}", a.jaccard(&b));
+        assert_eq!(a.jaccard(&minhasher.signature(&text_a)), 1.0);
+        // Word order and repetitions do not change the shingle set.
+        assert_eq!(a, minhasher.signature(&format!("{} {}", words[..80].iter().rev().cloned().collect::<Vec<_>>().join(" "), words[0])));
+
+        let index = LshIndex::for_threshold(256, 0.8);
+        assert!(index.bands() * index.rows() <= 256);
+        assert!((index.threshold() - 0.8).abs() < 0.05, "threshold {}", index.threshold());
+
+        let documents = [
+            "the quick brown fox jumps over the lazy dog near the river bank today",
+            "a completely different sentence about databases and query planners",
+            "the quick brown fox jumps over the lazy dog near the river bank",
+            "stock markets closed higher on friday after strong earnings reports",
+            "a completely different sentence about databases and query planners!",
+        ];
+        let minhasher = MinHasher::new(128, Shingling::Chars(4));
+        let mut index = LshIndex::for_threshold(128, 0.6);
+        for (i, document) in documents.iter().enumerate() {
+            assert_eq!(index.insert(minhasher.signature(document)), i);
+        }
+        assert_eq!(index.len(), 5);
+        let pairs: Vec<(usize, usize)> = index.near_duplicates(0.6).into_iter().map(|(a, b, _)| (a, b)).collect();
+        assert_eq!(pairs, vec![(0, 2), (1, 4)]);
+        assert!(index.candidate_pairs().contains(&(0, 2)));
+        assert_eq!(index.query(&minhasher.signature(documents[3])), vec![3]);
+        assert!(LshIndex::new(4, 8).is_empty());
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut filter = BloomFilter::new(10_000, 0.01).unwrap();
+        assert_eq!(filter.num_hashes(), 7);
+        assert_eq!(filter.num_bits(), 95_851);
+        for i in 0..10_000 {
+            filter.insert(&format!("member-{}", i));
+        }
+        assert!((0..10_000).all(|i| filter.contains(&format!("member-{}", i))));
+        let false_positives = (0..100_000).filter(|i| filter.contains(&format!("other-{}", i))).count();
+        let rate = false_positives as f64 / 100_000.0;
+        assert!(rate < 0.015, "false positive rate {}", rate);
+        assert!((filter.estimated_false_positive_rate() - 0.01).abs() < 0.002);
+
+        let mut shard_a = BloomFilter::with_params(4096, 3, 7).unwrap();
+        let mut shard_b = shard_a.clone();
+        shard_a.insert("a");
+        shard_b.insert(b"b");
+        shard_a.merge(&shard_b).unwrap();
+        assert!(shard_a.contains("a") && shard_a.contains("b"));
+        assert!(matches!(shard_a.merge(&BloomFilter::with_params(4096, 3, 8).unwrap()), Err(SketchError::Incompatible(_))));
+
+        let bytes = shard_a.to_bytes();
+        assert_eq!(bytes.len(), 5 + 24 + 512);
+        assert_eq!(BloomFilter::from_bytes(&bytes).unwrap(), shard_a);
+        assert!(matches!(BloomFilter::from_bytes(&bytes[..100]), Err(SketchError::InvalidFormat(_))));
+        assert!(BloomFilter::new(0, 0.01).is_err());
+        assert!(BloomFilter::new(100, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_cuckoo_filter() {
+        let mut filter = CuckooFilter::new(10_000).unwrap();
+        assert_eq!(filter.capacity(), 16_384);
+        // Load the filter to 90%, where the false positive bound still holds.
+        for i in 0..14_745 {
+            filter.insert(&format!("member-{}", i)).unwrap();
+        }
+        assert_eq!(filter.len(), 14_745);
+        assert!((0..14_745).all(|i| filter.contains(&format!("member-{}", i))));
+        let false_positives = (0..100_000).filter(|i| filter.contains(&format!("other-{}", i))).count();
+        assert!(false_positives as f64 / 100_000.0 < 8.0 / 65536.0 * 1.5, "{} false positives", false_positives);
+
+        assert!(filter.remove("member-3"));
+        assert!(!filter.contains("member-3"));
+        assert!(!filter.remove("member-3"));
+        assert_eq!(filter.len(), 14_744);
+
+        let bytes = filter.to_bytes();
+        assert_eq!(CuckooFilter::from_bytes(&bytes).unwrap(), filter);
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert!(matches!(CuckooFilter::from_bytes(&corrupt), Err(SketchError::InvalidFormat(_))));
+
+        // A full filter refuses new items and keeps the ones it has.
+        let mut small = CuckooFilter::with_seed(8, 1).unwrap();
+        let inserted: Vec<String> = (0..100).map(|i| format!("item-{}", i))
+            .take_while(|item| small.insert(item).is_ok())
+            .collect();
+        assert!(inserted.len() >= 8 && inserted.len() <= small.capacity());
+        assert_eq!(small.insert("one more"), Err(SketchError::Full));
+        assert!(inserted.iter().all(|item| small.contains(item)));
+
+        let mut shard_a = CuckooFilter::new(100).unwrap();
+        let mut shard_b = CuckooFilter::new(100).unwrap();
+        shard_a.insert("a").unwrap();
+        shard_b.insert("b").unwrap();
+        shard_a.merge(&shard_b).unwrap();
+        assert!(shard_a.contains("a") && shard_a.contains("b"));
+        assert_eq!(shard_a.len(), 2);
+        assert!(shard_a.merge(&CuckooFilter::new(1000).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_count_min_sketch() {
+        let (epsilon, delta) = (0.001, 0.01);
+        let mut sketch = CountMinSketch::new(epsilon, delta).unwrap();
+        assert_eq!((sketch.width(), sketch.depth()), (2719, 5));
+        // A skewed stream: item i appears 1 + 1000 / (i + 1) times.
+        let counts: Vec<u64> = (0..20_000).map(|i| 1 + 1000 / (i + 1)).collect();
+        for (i, &count) in counts.iter().enumerate() {
+            sketch.add(&format!("item-{}", i), count);
+        }
+        assert_eq!(sketch.total(), counts.iter().sum::<u64>());
+        let bound = epsilon * sketch.total() as f64;
+        let mut over = 0;
+        for (i, &count) in counts.iter().enumerate() {
+            let estimate = sketch.estimate(&format!("item-{}", i));
+            assert!(estimate >= count);
+            if (estimate - count) as f64 > bound {
+                over += 1;
+            }
+        }
+        assert!(over as f64 / counts.len() as f64 <= delta, "{} estimates over the bound", over);
+        assert_eq!(CountMinSketch::new(epsilon, delta).unwrap().estimate("missing"), 0);
+
+        let mut shard_a = CountMinSketch::with_dimensions(100, 4, 0).unwrap();
+        let mut shard_b = shard_a.clone();
+        shard_a.add("a", 2);
+        shard_b.add("a", 3);
+        shard_a.merge(&shard_b).unwrap();
+        assert_eq!(shard_a.estimate("a"), 5);
+        assert_eq!(shard_a.total(), 5);
+        assert!(shard_a.merge(&CountMinSketch::with_dimensions(100, 5, 0).unwrap()).is_err());
+
+        let bytes = shard_a.to_bytes();
+        assert_eq!(CountMinSketch::from_bytes(&bytes).unwrap(), shard_a);
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(CountMinSketch::from_bytes(&trailing), Err(SketchError::InvalidFormat(_))));
+        assert!(CountMinSketch::new(0.0, 0.1).is_err());
+    }
+
+    #[test]
+    fn test_hyperloglog() {
+        let mut hll = HyperLogLog::new(14).unwrap();
+        assert!((hll.standard_error() - 0.008125).abs() < 1e-6);
+        assert_eq!(hll.estimate(), 0.0);
+        for i in 0..100 {
+            hll.insert(&format!("user-{}", i));
+            hll.insert(&format!("user-{}", i));
+        }
+        assert!((hll.estimate() - 100.0).abs() < 2.0, "estimate {}", hll.estimate());
+
+        // Insert 1M distinct items over two shards, overlapping by 200k.
+        let mut shard_a = HyperLogLog::new(14).unwrap();
+        let mut shard_b = HyperLogLog::new(14).unwrap();
+        for i in 0..600_000u32 {
+            shard_a.insert(&i.to_le_bytes());
+        }
+        for i in 400_000..1_000_000u32 {
+            shard_b.insert(&i.to_le_bytes());
+        }
+        shard_a.merge(&shard_b).unwrap();
+        let error = (shard_a.estimate() - 1_000_000.0).abs() / 1_000_000.0;
+        assert!(error < 3.0 * shard_a.standard_error(), "relative error {}", error);
+        assert!(shard_a.merge(&HyperLogLog::new(12).unwrap()).is_err());
+
+        let bytes = shard_a.to_bytes();
+        assert_eq!(bytes.len(), 5 + 9 + 16_384);
+        assert_eq!(HyperLogLog::from_bytes(&bytes).unwrap(), shard_a);
+        let mut corrupt = bytes.clone();
+        corrupt[20] = 200;
+        assert!(matches!(HyperLogLog::from_bytes(&corrupt), Err(SketchError::InvalidFormat(_))));
+        assert!(HyperLogLog::new(3).is_err() && HyperLogLog::new(19).is_err());
+    }
+
+    #[test]
+    fn test_jump_hash() {
+        assert_eq!(sharding::jump_hash(0, 1), 0);
+        assert_eq!(sharding::jump_hash(1, 1), 0);
+        assert_eq!(sharding::jump_hash(0xdead_beef, 0), 0);
+        for key in 0..1000u64 {
+            let key = key.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            // Growing by one bucket either keeps the key or moves it to the new bucket.
+            for buckets in 1..20 {
+                let before = sharding::jump_hash(key, buckets);
+                let after = sharding::jump_hash(key, buckets + 1);
+                assert!(before < buckets);
+                assert!(after == before || after == buckets);
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistent_sharders() {
+        let keys: Vec<String> = (0..5000).map(|i| format!("key-{}", i)).collect();
+        let nodes: Vec<String> = (0..10).map(|i| format!("node-{}", i)).collect();
+
+        let mut ring = HashRing::new(HashAlgorithm::BLAKE3, 160);
+        let mut rendezvous = Rendezvous::new(HashAlgorithm::SHA2_256);
+        let mut jump = JumpHash::new(HashAlgorithm::SHA3_256);
+        let mut maglev = Maglev::with_table_size(HashAlgorithm::BLAKE2B, 5000);
+        assert_eq!(maglev.table_size(), 5003);
+        assert_eq!(ring.node_for("key"), None);
+        assert_eq!(maglev.node_for("key"), None);
+        for node in nodes.iter().take(9) {
+            assert!(ring.add_node(node));
+            assert!(rendezvous.add_node(node));
+            jump.push_node(node);
+            assert!(maglev.add_node(node));
+        }
+        assert!(!ring.add_node("node-0"));
+
+        // Every scheme spreads the keys evenly over the nodes.
+        fn shares<S: Sharder>(sharder: &S, keys: &[String]) -> Vec<usize> {
+            let mut counts = std::collections::BTreeMap::new();
+            for key in keys {
+                *counts.entry(sharder.node_for(key).unwrap().to_string()).or_insert(0) += 1;
+            }
+            counts.values().cloned().collect()
+        }
+        let mean = keys.len() / 9;
+        for counts in [shares(&ring, &keys), shares(&rendezvous, &keys), shares(&jump, &keys), shares(&maglev, &keys)].iter() {
+            assert_eq!(counts.len(), 9);
+            assert!(counts.iter().all(|&count| count > mean * 3 / 4 && count < mean * 5 / 4), "{:?}", counts);
+        }
+
+        // Adding a tenth node moves about a tenth of the keys, all to the new node.
+        let (ring_before, rendezvous_before, jump_before, maglev_before) =
+            (ring.clone(), rendezvous.clone(), jump.clone(), maglev.clone());
+        ring.add_node(&nodes[9]);
+        rendezvous.add_node(&nodes[9]);
+        jump.push_node(&nodes[9]);
+        maglev.add_node(&nodes[9]);
+        let movements = [
+            sharding::moved_keys(&ring_before, &ring, &keys),
+            sharding::moved_keys(&rendezvous_before, &rendezvous, &keys),
+            sharding::moved_keys(&jump_before, &jump, &keys),
+            sharding::moved_keys(&maglev_before, &maglev, &keys),
+        ];
+        for movement in movements.iter() {
+            assert_eq!(movement.total, 5000);
+            assert!(movement.fraction() > 0.07 && movement.fraction() < 0.15, "{:?}", movement);
+        }
+        for key in keys.iter() {
+            assert!(ring.node_for(key) == ring_before.node_for(key) || ring.node_for(key) == Some("node-9"));
+            assert!(rendezvous.node_for(key) == rendezvous_before.node_for(key)
+                || rendezvous.node_for(key) == Some("node-9"));
+            assert!(jump.node_for(key) == jump_before.node_for(key) || jump.node_for(key) == Some("node-9"));
+        }
+
+        // Removing the node again restores the previous assignment.
+        assert!(ring.remove_node("node-9") && rendezvous.remove_node("node-9") && maglev.remove_node("node-9"));
+        assert_eq!(jump.pop_node(), Some("node-9".to_string()));
+        assert_eq!(sharding::moved_keys(&ring_before, &ring, &keys).moved, 0);
+        assert_eq!(sharding::moved_keys(&rendezvous_before, &rendezvous, &keys).moved, 0);
+        assert_eq!(sharding::moved_keys(&jump_before, &jump, &keys).moved, 0);
+        assert_eq!(sharding::moved_keys(&maglev_before, &maglev, &keys).moved, 0);
+        assert!(!maglev.remove_node("node-9"));
+
+        // The replicas of a key are the nodes that would take it over.
+        let ranked = rendezvous.ranked_nodes("key-1");
+        assert_eq!(ranked.len(), 9);
+        assert_eq!(Some(ranked[0]), rendezvous.node_for("key-1"));
+        rendezvous.remove_node(ranked[0].to_string().as_str());
+        assert_eq!(Some(rendezvous.ranked_nodes("key-1")[0]), rendezvous.node_for("key-1"));
+    }
+
+    #[test]
+    fn test_percentage_buckets() {
+        let algorithm = HashAlgorithm::SHA2_256;
+        let positions: Vec<f64> = (0..10_000)
+            .map(|i| sharding::percentage_bucket(algorithm, "experiment", &format!("user:{}", i)))
+            .collect();
+        assert!(positions.iter().all(|&position| (0.0..100.0).contains(&position)));
+        let in_ten = positions.iter().filter(|&&position| position < 10.0).count();
+        assert!(in_ten > 900 && in_ten < 1100, "{} in the first 10%", in_ten);
+        assert_eq!(sharding::percentage_bucket(algorithm, "experiment", "user:0"), positions[0]);
+        // Raising the percentage only adds users.
+        for i in 0..1000 {
+            let user = format!("user:{}", i);
+            if sharding::in_percentage(algorithm, "experiment", &user, 5.0) {
+                assert!(sharding::in_percentage(algorithm, "experiment", &user, 20.0));
+            }
+        }
+        // Different experiments are independent.
+        let both = (0..10_000)
+            .filter(|i| sharding::in_percentage(algorithm, "experiment", &format!("user:{}", i), 50.0)
+                && sharding::in_percentage(algorithm, "other", &format!("user:{}", i), 50.0))
+            .count();
+        assert!(both > 2300 && both < 2700, "{} in both halves", both);
+    }
+
+    #[test]
+    fn test_password_known_hashes() {
+        // From the reference implementation of Argon2, and Python's hashlib for the others.
+        let known = [
+            ("password", "$argon2id$v=19$m=65536,t=2,p=1$c29tZXNhbHQ$CTFhFdXPJO1aFaMaO6Mm5c8y7cJHAph8ArZWb2GRPPc",
+             PasswordParams::Argon2id { memory_kib: 65536, iterations: 2, parallelism: 1 }),
+            ("hunter2", "$scrypt$ln=10,r=8,p=1$c2FsdHNhbHRzYWx0$P5X0OkxrtLAzgYYm9Bf3w2w3CQNvcIgO6gb99vmKzSs",
+             PasswordParams::Scrypt { log_n: 10, r: 8, p: 1 }),
+            ("hunter2", "$pbkdf2-sha256$i=1000,l=32$c2FsdHNhbHRzYWx0$sWL6nKqk2l/lras1LBJAL5VKXy/eS0lorHQFrTK1Tlw",
+             PasswordParams::Pbkdf2Sha256 { rounds: 1000 }),
+            ("hunter2", "$pbkdf2-sha512$i=1000,l=64$c2FsdHNhbHRzYWx0$FeKtCdkl0XH9475BIO7EfQdI+pwfkXXSTjoJn/jyv1LalbYjF37dx4VS3kVL8zey5YxYOUt0wnoekmM0E72loQ",
+             PasswordParams::Pbkdf2Sha512 { rounds: 1000 }),
+            // From the OpenBSD test vectors.
+            ("U*U", "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW", PasswordParams::Bcrypt { cost: 5 }),
+        ];
+        for (plain, hash, params) in known.iter() {
+            assert_eq!(password::verify_password(plain, hash), Ok(true), "{}", hash);
+            assert_eq!(password::verify_password("wrong", hash), Ok(false), "{}", hash);
+            assert_eq!(PasswordParams::from_hash(hash), Ok(*params));
+            assert_eq!(password::needs_rehash(hash, params), Ok(false));
+        }
+
+        assert!(matches!(password::verify_password("x", "not a hash"), Err(PasswordError::InvalidHash(_))));
+        assert!(matches!(password::verify_password("x", "$2b$05$tooshort"), Err(PasswordError::InvalidHash(_))));
+        // PBKDF2-HMAC-SHA1 is not supported.
+        assert_eq!(password::verify_password("x", "$pbkdf2$i=1000,l=20$c2FsdHNhbHRzYWx0$AAAAAAAAAAAAAAAAAAAAAAAAAAA"),
+                   Err(PasswordError::UnsupportedAlgorithm("pbkdf2".to_string())));
+    }
+
+    #[test]
+    fn test_password_hashing() {
+        let all_params = [
+            PasswordParams::Argon2id { memory_kib: 1024, iterations: 1, parallelism: 2 },
+            PasswordParams::Scrypt { log_n: 8, r: 8, p: 1 },
+            PasswordParams::Pbkdf2Sha256 { rounds: 1000 },
+            PasswordParams::Pbkdf2Sha512 { rounds: 1000 },
+            PasswordParams::Bcrypt { cost: 4 },
+        ];
+        let prefixes = ["$argon2id$v=19$m=1024,t=1,p=2$", "$scrypt$ln=8,r=8,p=1$", "$pbkdf2-sha256$i=1000,l=32$",
+                        "$pbkdf2-sha512$i=1000,l=64$", "$2b$04$"];
+        for (params, prefix) in all_params.iter().zip(prefixes.iter()) {
+            let hash = password::hash_password("pässwörd", params).unwrap();
+            assert!(hash.starts_with(prefix), "{}", hash);
+            assert_eq!(password::verify_password("pässwörd", &hash), Ok(true));
+            assert_eq!(password::verify_password("passwörd", &hash), Ok(false));
+            // Every hash gets a new salt.
+            assert_ne!(password::hash_password("pässwörd", params).unwrap(), hash);
+
+            assert_eq!(password::needs_rehash(&hash, params), Ok(false));
+            for other in all_params.iter().filter(|&other| other != params) {
+                assert_eq!(password::needs_rehash(&hash, other), Ok(true));
+            }
+        }
+        assert_eq!(PasswordParams::default(), PasswordParams::argon2id());
+        let weaker = password::hash_password("secret", &PasswordParams::Pbkdf2Sha256 { rounds: 1000 }).unwrap();
+        assert_eq!(password::needs_rehash(&weaker, &PasswordParams::pbkdf2_sha256()), Ok(true));
+
+        // bcrypt only uses 72 bytes with the terminating NUL, so longer passwords are rejected.
+        let long = "a".repeat(72);
+        assert_eq!(password::hash_password(&long, &PasswordParams::Bcrypt { cost: 4 }), Err(PasswordError::PasswordTooLong));
+        let bcrypt_hash = password::hash_password(&long[..71], &PasswordParams::Bcrypt { cost: 4 }).unwrap();
+        assert_eq!(password::verify_password(&long, &bcrypt_hash), Ok(false));
+
+        assert!(matches!(password::hash_password("x", &PasswordParams::Bcrypt { cost: 3 }),
+                         Err(PasswordError::InvalidParameter(_))));
+        assert!(matches!(password::hash_password("x", &PasswordParams::Argon2id { memory_kib: 1, iterations: 1, parallelism: 1 }),
+                         Err(PasswordError::InvalidParameter(_))));
+        assert!(matches!(password::hash_password("x", &PasswordParams::Pbkdf2Sha256 { rounds: 0 }),
+                         Err(PasswordError::InvalidParameter(_))));
+    }
+
+    fn ct_leaves() -> Vec<Vec<u8>> {
+        vec![vec![], vec![0x00], vec![0x10], vec![0x20, 0x21], vec![0x30, 0x31], vec![0x40, 0x41, 0x42, 0x43],
+             (0x50..0x58).collect(), (0x60..0x70).collect()]
+    }
+
+    fn sha256(hex: &str) -> Digest {
+        Digest::from_hex(HashAlgorithm::SHA2_256, hex).unwrap()
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        // The test vectors of Certificate Transparency.
+        let roots = [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ];
+        let tree = MerkleTree::from_leaves(HashAlgorithm::SHA2_256, ct_leaves());
+        assert_eq!(tree.len(), 8);
+        for (size, root) in roots.iter().enumerate() {
+            assert_eq!(tree.root_at(size + 1), Some(sha256(root)));
+        }
+        assert_eq!(tree.root(), sha256(roots[7]));
+        assert_eq!(tree.root_at(9), None);
+        assert_eq!(MerkleTree::new(HashAlgorithm::SHA2_256).root(), hash_string(HashAlgorithm::SHA2_256, ""));
+        assert_eq!(tree.leaf_hash(0), Some(&merkle::leaf_hash(HashAlgorithm::SHA2_256, b"")));
+
+        let mut pushed = MerkleTree::new(HashAlgorithm::SHA2_256);
+        for leaf in ct_leaves() {
+            pushed.push(&leaf);
+        }
+        assert_eq!(pushed, tree);
+    }
+
+    #[test]
+    fn test_merkle_inclusion_proof() {
+        let tree = MerkleTree::from_leaves(HashAlgorithm::SHA2_256, ct_leaves());
+        let proof = tree.inclusion_proof(2).unwrap();
+        assert_eq!(proof.path, vec![
+            sha256("07506a85fd9dd2f120eb694f86011e5bb4662e5c415a62917033d4a9624487e7"),
+            sha256("fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"),
+            sha256("6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4"),
+        ]);
+        assert!(tree.inclusion_proof(8).is_none());
+
+        let leaves = ct_leaves();
+        for size in 1..=8 {
+            let subtree = MerkleTree::from_leaves(HashAlgorithm::SHA2_256, &leaves[..size]);
+            let root = subtree.root();
+            for (index, leaf) in leaves[..size].iter().enumerate() {
+                let proof = subtree.inclusion_proof(index).unwrap();
+                assert!(proof.verify(leaf, &root));
+                assert!(!proof.verify(b"other", &root));
+                assert!(!proof.verify(leaf, &sha256(&"00".repeat(32))));
+
+                let mut moved = proof.clone();
+                moved.leaf_index = (index as u64 + 1) % size as u64;
+                assert!(size == 1 || !moved.verify(leaf, &root));
+                let mut outside = proof.clone();
+                outside.leaf_index = size as u64;
+                assert!(!outside.verify(leaf, &root));
+                if !proof.path.is_empty() {
+                    let mut short = proof.clone();
+                    short.path.pop();
+                    assert!(!short.verify(leaf, &root));
+                }
+                let mut long = proof.clone();
+                long.path.push(root.clone());
+                assert!(!long.verify(leaf, &root));
+            }
+        }
+    }
+
+    #[test]
+    fn test_merkle_consistency_proof() {
+        let tree = MerkleTree::from_leaves(HashAlgorithm::SHA2_256, ct_leaves());
+        let tree7 = MerkleTree::from_leaves(HashAlgorithm::SHA2_256, &ct_leaves()[..7]);
+        assert_eq!(tree7.consistency_proof(3).unwrap().path, vec![
+            sha256("0298d122906dcfc10892cb53a73992fc5b9f493ea4c9badb27b791b4127a7fe7"),
+            sha256("07506a85fd9dd2f120eb694f86011e5bb4662e5c415a62917033d4a9624487e7"),
+            sha256("fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"),
+            sha256("837dbb152e9b079010717e84e865da4ebc0fa198a806d59d31bf15accef22d0e"),
+        ]);
+        assert!(tree.consistency_proof(9).is_none());
+
+        let leaves = ct_leaves();
+        for new_size in 0..=8 {
+            let new_tree = MerkleTree::from_leaves(HashAlgorithm::SHA2_256, &leaves[..new_size]);
+            let new_root = new_tree.root();
+            for old_size in 0..=new_size {
+                let old_root = tree.root_at(old_size).unwrap();
+                let proof = new_tree.consistency_proof(old_size).unwrap();
+                assert_eq!((proof.old_size, proof.new_size), (old_size as u64, new_size as u64));
+                assert!(proof.verify(&old_root, &new_root));
+                if old_size == 0 {
+                    continue;
+                }
+                // A different old tree of the same size is not consistent.
+                let mut forked = leaves[..old_size].to_vec();
+                forked[old_size - 1] = b"forked".to_vec();
+                let forked_root = MerkleTree::from_leaves(HashAlgorithm::SHA2_256, forked).root();
+                assert!(!proof.verify(&forked_root, &new_root));
+                assert!(!proof.verify(&old_root, &forked_root));
+
+                if !proof.path.is_empty() {
+                    let mut tampered = proof.clone();
+                    tampered.path[0] = sha256(&"00".repeat(32));
+                    assert!(!tampered.verify(&old_root, &new_root));
+                    let mut short = proof.clone();
+                    short.path.pop();
+                    assert!(!short.verify(&old_root, &new_root));
+                }
+                let mut long = proof.clone();
+                long.path.push(new_root.clone());
+                assert!(!long.verify(&old_root, &new_root));
+            }
+        }
     }
 }
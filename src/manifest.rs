@@ -0,0 +1,296 @@
+//! This module reads, writes and verifies checksum manifests.
+//!
+//! Two formats are supported:
+//! 1. The GNU coreutils format used by `sha256sum` and friends: `<hex>  <file>`, where the
+//!    second separator character is `*` for files hashed in binary mode. Filenames containing
+//!    a backslash, a newline or a carriage return are escaped and the line starts with `\`.
+//! 2. The BSD tag format (`sha256sum --tag`): `SHA256 (<file>) = <hex>`.
+//!
+//! The GNU format does not record the hash algorithm, so it has to be given when parsing.
+//! The BSD format carries it in the tag.
+//!
+//! ```
+//! use estahr::hash::HashAlgorithm;
+//! use estahr::manifest::{self, ManifestFormat};
+//!
+//! let input = "ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb  a.txt\n";
+//! let entries = manifest::parse(input, HashAlgorithm::SHA2_256).unwrap();
+//! assert_eq!(entries[0].path.to_str(), Some("a.txt"));
+//! assert_eq!(manifest::render(&entries, ManifestFormat::Bsd),
+//!            "SHA256 (a.txt) = ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb\n");
+//! ```
+
+use std::{error, fmt, io};
+use std::path::{Path, PathBuf};
+
+use crate::hash::{hash_file, Digest, HashAlgorithm};
+
+/// The layout of a manifest line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// GNU coreutils format: `<hex>  <file>`.
+    Gnu,
+    /// BSD tag format: `SHA256 (<file>) = <hex>`.
+    Bsd,
+}
+
+/// A single file and its expected digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub digest: Digest,
+    /// Whether the file was hashed in binary mode (the `*` marker of the GNU format).
+    ///
+    /// This is informational only, files are always hashed byte for byte.
+    pub binary: bool,
+}
+
+/// The error returned when a manifest line cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestParseError {
+    /// The line number, starting at 1.
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ManifestParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid manifest line {}: {}", self.line, self.reason)
+    }
+}
+
+impl error::Error for ManifestParseError {}
+
+/// The outcome of verifying one manifest entry.
+#[derive(Debug)]
+pub enum VerifyStatus {
+    /// The file matches its digest.
+    Ok,
+    /// The file was read, but its digest is different.
+    Failed,
+    /// The file does not exist.
+    Missing,
+    /// The file exists but could not be read.
+    Unreadable(io::Error),
+}
+
+/// The result of verifying one manifest entry.
+#[derive(Debug)]
+pub struct VerifyResult {
+    pub path: PathBuf,
+    pub status: VerifyStatus,
+}
+
+impl VerifyResult {
+    /// Returns true if the file matches its digest.
+    pub fn is_ok(&self) -> bool {
+        matches!(self.status, VerifyStatus::Ok)
+    }
+}
+
+fn bsd_tag(hash_algorithm: HashAlgorithm) -> &'static str {
+    match hash_algorithm {
+        HashAlgorithm::MD5 => "MD5",
+        HashAlgorithm::SHA2_256 => "SHA256",
+        HashAlgorithm::SHA2_512 => "SHA512",
+        HashAlgorithm::SHA3_256 => "SHA3-256",
+        HashAlgorithm::SHA3_512 => "SHA3-512",
+        HashAlgorithm::BLAKE2S => "BLAKE2s",
+        HashAlgorithm::BLAKE2B => "BLAKE2b",
+        HashAlgorithm::BLAKE3 => "BLAKE3",
+        HashAlgorithm::SHA1 => "SHA1",
+        HashAlgorithm::SHA2_224 => "SHA224",
+        HashAlgorithm::SHA2_384 => "SHA384",
+        HashAlgorithm::SHA2_512_256 => "SHA512/256",
+        HashAlgorithm::SHA3_224 => "SHA3-224",
+        HashAlgorithm::SHA3_384 => "SHA3-384",
+        HashAlgorithm::KECCAK256 => "KECCAK256",
+        HashAlgorithm::RIPEMD160 => "RMD160",
+        HashAlgorithm::WHIRLPOOL => "WHIRLPOOL",
+    }
+}
+
+fn algorithm_from_bsd_tag(tag: &str) -> Option<HashAlgorithm> {
+    match tag {
+        "MD5" => Some(HashAlgorithm::MD5),
+        "SHA256" => Some(HashAlgorithm::SHA2_256),
+        "SHA512" => Some(HashAlgorithm::SHA2_512),
+        "SHA3-256" => Some(HashAlgorithm::SHA3_256),
+        "SHA3-512" => Some(HashAlgorithm::SHA3_512),
+        "BLAKE2s" => Some(HashAlgorithm::BLAKE2S),
+        "BLAKE2b" => Some(HashAlgorithm::BLAKE2B),
+        "BLAKE3" => Some(HashAlgorithm::BLAKE3),
+        "SHA1" => Some(HashAlgorithm::SHA1),
+        "SHA224" => Some(HashAlgorithm::SHA2_224),
+        "SHA384" => Some(HashAlgorithm::SHA2_384),
+        "SHA512/256" => Some(HashAlgorithm::SHA2_512_256),
+        "SHA3-224" => Some(HashAlgorithm::SHA3_224),
+        "SHA3-384" => Some(HashAlgorithm::SHA3_384),
+        "KECCAK256" => Some(HashAlgorithm::KECCAK256),
+        "RMD160" => Some(HashAlgorithm::RIPEMD160),
+        "WHIRLPOOL" => Some(HashAlgorithm::WHIRLPOOL),
+        _ => None,
+    }
+}
+
+fn needs_escape(name: &str) -> bool {
+    name.contains(['\\', '\n', '\r'])
+}
+
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(name: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => return Err(format!("unknown escape sequence '\\{}'", other)),
+            None => return Err("dangling escape character".to_string()),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Hashes every one of the given files with the selected hash algorithm.
+///
+/// The paths are recorded as given. Returns the first `io::Error` encountered.
+pub fn generate<I, P>(hash_algorithm: HashAlgorithm, paths: I) -> io::Result<Vec<ManifestEntry>>
+    where I: IntoIterator<Item=P>, P: AsRef<Path> {
+    paths.into_iter()
+        .map(|path| {
+            let path = path.as_ref();
+            Ok(ManifestEntry { path: path.to_path_buf(), digest: hash_file(hash_algorithm, path)?, binary: false })
+        })
+        .collect()
+}
+
+/// Renders the entries as a manifest in the given format, one line per entry.
+///
+/// Paths that are not valid UTF-8 are written lossily.
+pub fn render(entries: &[ManifestEntry], format: ManifestFormat) -> String {
+    let mut output = String::new();
+    for entry in entries {
+        let name = entry.path.to_string_lossy();
+        let (prefix, name) = if needs_escape(&name) {
+            ("\\", escape(&name))
+        } else {
+            ("", name.into_owned())
+        };
+        match format {
+            ManifestFormat::Gnu => {
+                let marker = if entry.binary { '*' } else { ' ' };
+                output.push_str(&format!("{}{} {}{}\n", prefix, entry.digest.to_hex(), marker, name));
+            }
+            ManifestFormat::Bsd => {
+                let tag = bsd_tag(entry.digest.algorithm());
+                output.push_str(&format!("{}{} ({}) = {}\n", prefix, tag, name, entry.digest.to_hex()));
+            }
+        }
+    }
+    output
+}
+
+fn parse_gnu_line(line: &str, hash_algorithm: HashAlgorithm) -> Result<ManifestEntry, String> {
+    let separator = line.find(' ').ok_or("missing separator")?;
+    let (hex, rest) = line.split_at(separator);
+    let mut rest = rest[1..].chars();
+    let binary = match rest.next() {
+        Some('*') => true,
+        Some(' ') => false,
+        _ => return Err("expected ' ' or '*' after the digest".to_string()),
+    };
+    let name = rest.as_str();
+    if name.is_empty() {
+        return Err("missing file name".to_string());
+    }
+    let digest = Digest::from_hex(hash_algorithm, hex).map_err(|e| e.to_string())?;
+    Ok(ManifestEntry { path: PathBuf::from(name), digest, binary })
+}
+
+fn parse_bsd_line(line: &str) -> Result<ManifestEntry, String> {
+    let open = line.find(" (").ok_or("missing '('")?;
+    let close = line.rfind(") = ").ok_or("missing ') = '")?;
+    if close < open {
+        return Err("malformed tag line".to_string());
+    }
+    let tag = &line[..open];
+    let hash_algorithm = algorithm_from_bsd_tag(tag).ok_or_else(|| format!("unknown algorithm tag '{}'", tag))?;
+    let name = &line[open + 2..close];
+    let digest = Digest::from_hex(hash_algorithm, &line[close + 4..]).map_err(|e| e.to_string())?;
+    Ok(ManifestEntry { path: PathBuf::from(name), digest, binary: false })
+}
+
+fn is_bsd_line(line: &str) -> bool {
+    match line.find(" (") {
+        Some(open) => !line[..open].contains(' ') && line.contains(") = "),
+        None => false,
+    }
+}
+
+/// Parses a manifest in either format. Both formats can be mixed in the same manifest.
+///
+/// GNU lines are parsed as digests of `hash_algorithm`; BSD lines use the algorithm of their
+/// tag. Empty lines and lines starting with `#` are skipped.
+pub fn parse(input: &str, hash_algorithm: HashAlgorithm) -> Result<Vec<ManifestEntry>, ManifestParseError> {
+    let mut entries = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (escaped, line) = match line.strip_prefix('\\') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let parsed = if is_bsd_line(line) {
+            parse_bsd_line(line)
+        } else {
+            parse_gnu_line(line, hash_algorithm)
+        };
+        let mut entry = parsed.map_err(|reason| ManifestParseError { line: index + 1, reason })?;
+        if escaped {
+            let name = unescape(&entry.path.to_string_lossy())
+                .map_err(|reason| ManifestParseError { line: index + 1, reason })?;
+            entry.path = PathBuf::from(name);
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Verifies every entry of a manifest, hashing each file again.
+///
+/// Relative paths are resolved against `base_dir`. Every entry is checked, whatever the
+/// outcome of the previous ones, and the results are returned in the same order.
+pub fn verify<P: AsRef<Path>>(entries: &[ManifestEntry], base_dir: P) -> Vec<VerifyResult> {
+    let base_dir = base_dir.as_ref();
+    entries.iter()
+        .map(|entry| {
+            let status = match hash_file(entry.digest.algorithm(), base_dir.join(&entry.path)) {
+                Ok(digest) if digest.ct_eq(&entry.digest) => VerifyStatus::Ok,
+                Ok(_) => VerifyStatus::Failed,
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => VerifyStatus::Missing,
+                Err(e) => VerifyStatus::Unreadable(e),
+            };
+            VerifyResult { path: entry.path.clone(), status }
+        })
+        .collect()
+}
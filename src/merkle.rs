@@ -0,0 +1,360 @@
+//! This module builds Merkle trees, to prove that a piece of data belongs to a larger dataset
+//! without sending the whole dataset.
+//!
+//! The trees follow RFC 6962 (Certificate Transparency), over any `HashAlgorithm`. Leaves are
+//! hashed as `H(0x00 || data)` and interior nodes as `H(0x01 || left || right)`, so a leaf
+//! can never be passed off as a node. A tree of `n` leaves has the first `k` leaves on its
+//! left, where `k` is the largest power of two smaller than `n`, and the rest on its right.
+//! The root of an empty tree is the hash of the empty string.
+//!
+//! Two kinds of proofs are available, both of about `log2(n)` hashes:
+//! 1. An `InclusionProof` shows that a leaf is at a given index of a tree with a given root.
+//! 2. A `ConsistencyProof` shows that a tree is an append-only extension of an older one:
+//!    the first leaves of the new tree are exactly the leaves of the old tree.
+//!
+//! Verifying a proof only needs the proof, the roots and the leaf, not the tree.
+//!
+//! ```
+//! use estahr::hash::HashAlgorithm;
+//! use estahr::merkle::MerkleTree;
+//!
+//! let tree = MerkleTree::from_leaves(HashAlgorithm::SHA2_256, &["a", "b", "c", "d", "e"]);
+//! let root = tree.root();
+//!
+//! let proof = tree.inclusion_proof(2).unwrap();
+//! assert!(proof.verify("c", &root));
+//! assert!(!proof.verify("x", &root));
+//! ```
+
+use std::{fs, io};
+use std::path::Path;
+
+use crate::hash::{hash_bytes, Digest, HashAlgorithm, Hasher};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Hashes the data of a leaf: `H(0x00 || data)`.
+pub fn leaf_hash(hash_algorithm: HashAlgorithm, data: &[u8]) -> Digest {
+    let mut hasher = Hasher::new(hash_algorithm);
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Hashes two children into their parent node: `H(0x01 || left || right)`.
+pub fn node_hash(hash_algorithm: HashAlgorithm, left: &Digest, right: &Digest) -> Digest {
+    let mut hasher = Hasher::new(hash_algorithm);
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+/// The largest power of two smaller than `n`, for `n > 1`.
+fn split_point(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+/// A Merkle tree, as defined by RFC 6962.
+///
+/// The tree keeps the hashes of its leaves, and leaves can be appended with `push`. The
+/// roots and proofs are computed from the leaf hashes when asked for, in time linear in the
+/// number of leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    algorithm: HashAlgorithm,
+    leaves: Vec<Digest>,
+}
+
+impl MerkleTree {
+    /// Creates an empty tree.
+    pub fn new(hash_algorithm: HashAlgorithm) -> MerkleTree {
+        MerkleTree { algorithm: hash_algorithm, leaves: Vec::new() }
+    }
+
+    /// Creates a tree with the given leaves, in order.
+    pub fn from_leaves<I>(hash_algorithm: HashAlgorithm, leaves: I) -> MerkleTree
+        where I: IntoIterator, I::Item: AsRef<[u8]> {
+        let mut tree = MerkleTree::new(hash_algorithm);
+        for leaf in leaves {
+            tree.push(leaf.as_ref());
+        }
+        tree
+    }
+
+    /// Creates a tree whose leaves are the blocks of `block_size` bytes read from the reader,
+    /// until EOF. The last block can be shorter, and an empty input gives an empty tree.
+    ///
+    /// Returns an `io::Error` if any read fails, or of kind `InvalidInput` if the block size
+    /// is 0.
+    pub fn from_reader<R: io::Read>(hash_algorithm: HashAlgorithm, mut reader: R, block_size: usize) -> io::Result<MerkleTree> {
+        if block_size == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the block size must be positive"));
+        }
+        let mut tree = MerkleTree::new(hash_algorithm);
+        let mut block = vec![0; block_size];
+        loop {
+            let mut filled = 0;
+            while filled < block_size {
+                match reader.read(&mut block[filled..]) {
+                    Ok(0) => break,
+                    Ok(read) => filled += read,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+            if filled > 0 {
+                tree.push(&block[..filled]);
+            }
+            if filled < block_size {
+                return Ok(tree);
+            }
+        }
+    }
+
+    /// Creates a tree whose leaves are the blocks of `block_size` bytes of the given file, see
+    /// `from_reader`.
+    ///
+    /// Returns an `io::Error` if the file cannot be opened for any reason.
+    ///
+    /// ```no_run
+    /// use estahr::hash::HashAlgorithm;
+    /// use estahr::merkle::MerkleTree;
+    ///
+    /// let tree = MerkleTree::from_file(HashAlgorithm::SHA2_256, "dataset.bin", 1024 * 1024).unwrap();
+    /// println!("{} blocks, root {}", tree.len(), tree.root());
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(hash_algorithm: HashAlgorithm, path: P, block_size: usize) -> io::Result<MerkleTree> {
+        MerkleTree::from_reader(hash_algorithm, fs::File::open(path)?, block_size)
+    }
+
+    /// The hash algorithm of the tree.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// The number of leaves.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns true if the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Appends a leaf to the tree.
+    pub fn push<T: AsRef<[u8]> + ?Sized>(&mut self, leaf: &T) {
+        self.leaves.push(leaf_hash(self.algorithm, leaf.as_ref()));
+    }
+
+    /// The hash of the leaf at the given index, or `None` if it is out of range.
+    pub fn leaf_hash(&self, index: usize) -> Option<&Digest> {
+        self.leaves.get(index)
+    }
+
+    /// The root hash of the tree.
+    pub fn root(&self) -> Digest {
+        self.subtree_root(&self.leaves)
+    }
+
+    /// The root hash of the tree made of the first `size` leaves, or `None` if the tree has
+    /// fewer leaves.
+    pub fn root_at(&self, size: usize) -> Option<Digest> {
+        self.leaves.get(..size).map(|leaves| self.subtree_root(leaves))
+    }
+
+    fn subtree_root(&self, leaves: &[Digest]) -> Digest {
+        match leaves.len() {
+            0 => hash_bytes(self.algorithm, &[]),
+            1 => leaves[0].clone(),
+            n => {
+                let (left, right) = leaves.split_at(split_point(n));
+                node_hash(self.algorithm, &self.subtree_root(left), &self.subtree_root(right))
+            }
+        }
+    }
+
+    /// Builds the proof that the leaf at `index` is in the tree, or `None` if the index is
+    /// out of range.
+    pub fn inclusion_proof(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        self.inclusion_path(index, &self.leaves, &mut path);
+        Some(InclusionProof {
+            algorithm: self.algorithm,
+            leaf_index: index as u64,
+            tree_size: self.leaves.len() as u64,
+            path,
+        })
+    }
+
+    /// `PATH(m, D[n])` of RFC 6962, from the leaf up.
+    fn inclusion_path(&self, index: usize, leaves: &[Digest], path: &mut Vec<Digest>) {
+        if leaves.len() <= 1 {
+            return;
+        }
+        let (left, right) = leaves.split_at(split_point(leaves.len()));
+        if index < left.len() {
+            self.inclusion_path(index, left, path);
+            path.push(self.subtree_root(right));
+        } else {
+            self.inclusion_path(index - left.len(), right, path);
+            path.push(self.subtree_root(left));
+        }
+    }
+
+    /// Builds the proof that the tree is an extension of the tree made of its first
+    /// `old_size` leaves, or `None` if the tree has fewer leaves.
+    pub fn consistency_proof(&self, old_size: usize) -> Option<ConsistencyProof> {
+        if old_size > self.leaves.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        if old_size > 0 {
+            self.consistency_path(old_size, &self.leaves, true, &mut path);
+        }
+        Some(ConsistencyProof {
+            algorithm: self.algorithm,
+            old_size: old_size as u64,
+            new_size: self.leaves.len() as u64,
+            path,
+        })
+    }
+
+    /// `SUBPROOF(m, D[n], b)` of RFC 6962.
+    fn consistency_path(&self, old_size: usize, leaves: &[Digest], complete: bool, path: &mut Vec<Digest>) {
+        if old_size == leaves.len() {
+            if !complete {
+                path.push(self.subtree_root(leaves));
+            }
+            return;
+        }
+        let (left, right) = leaves.split_at(split_point(leaves.len()));
+        if old_size <= left.len() {
+            self.consistency_path(old_size, left, complete, path);
+            path.push(self.subtree_root(right));
+        } else {
+            self.consistency_path(old_size - left.len(), right, false, path);
+            path.push(self.subtree_root(left));
+        }
+    }
+}
+
+/// The proof that a leaf is in a tree, returned by `MerkleTree::inclusion_proof`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+    /// The hash algorithm of the tree.
+    pub algorithm: HashAlgorithm,
+    /// The index of the leaf.
+    pub leaf_index: u64,
+    /// The number of leaves of the tree.
+    pub tree_size: u64,
+    /// The hashes of the siblings on the way from the leaf to the root.
+    pub path: Vec<Digest>,
+}
+
+impl InclusionProof {
+    /// Returns true if the proof shows that the leaf data is at `leaf_index` in the tree of
+    /// `tree_size` leaves with the given root.
+    pub fn verify<T: AsRef<[u8]> + ?Sized>(&self, leaf: &T, root: &Digest) -> bool {
+        self.verify_leaf_hash(&leaf_hash(self.algorithm, leaf.as_ref()), root)
+    }
+
+    /// Like `verify`, with the hash of the leaf instead of its data.
+    pub fn verify_leaf_hash(&self, leaf_hash: &Digest, root: &Digest) -> bool {
+        // The algorithm of RFC 9162, section 2.1.3.2.
+        if self.leaf_index >= self.tree_size {
+            return false;
+        }
+        let (mut index, mut last) = (self.leaf_index, self.tree_size - 1);
+        let mut hash = leaf_hash.clone();
+        for sibling in self.path.iter() {
+            if last == 0 {
+                return false;
+            }
+            if index & 1 == 1 || index == last {
+                hash = node_hash(self.algorithm, sibling, &hash);
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                hash = node_hash(self.algorithm, &hash, sibling);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        last == 0 && hash == *root
+    }
+}
+
+/// The proof that a tree is an append-only extension of an older tree, returned by
+/// `MerkleTree::consistency_proof`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    /// The hash algorithm of the trees.
+    pub algorithm: HashAlgorithm,
+    /// The number of leaves of the old tree.
+    pub old_size: u64,
+    /// The number of leaves of the new tree.
+    pub new_size: u64,
+    /// The hashes of the subtrees needed to rebuild both roots.
+    pub path: Vec<Digest>,
+}
+
+impl ConsistencyProof {
+    /// Returns true if the proof shows that the tree of `new_size` leaves with `new_root`
+    /// starts with the leaves of the tree of `old_size` leaves with `old_root`.
+    ///
+    /// An empty old tree is consistent with any tree.
+    pub fn verify(&self, old_root: &Digest, new_root: &Digest) -> bool {
+        // The algorithm of RFC 9162, section 2.1.4.2.
+        let (old_size, new_size) = (self.old_size, self.new_size);
+        if old_size > new_size {
+            return false;
+        }
+        if old_size == 0 {
+            return self.path.is_empty();
+        }
+        if old_size == new_size {
+            return self.path.is_empty() && old_root == new_root;
+        }
+        let mut path = self.path.iter();
+        let first = if old_size.is_power_of_two() {
+            old_root
+        } else {
+            match path.next() {
+                Some(first) => first,
+                None => return false,
+            }
+        };
+        let (mut index, mut last) = (old_size - 1, new_size - 1);
+        while index & 1 == 1 {
+            index >>= 1;
+            last >>= 1;
+        }
+        let (mut old_hash, mut new_hash) = (first.clone(), first.clone());
+        for sibling in path {
+            if last == 0 {
+                return false;
+            }
+            if index & 1 == 1 || index == last {
+                old_hash = node_hash(self.algorithm, sibling, &old_hash);
+                new_hash = node_hash(self.algorithm, sibling, &new_hash);
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                new_hash = node_hash(self.algorithm, &new_hash, sibling);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        last == 0 && old_hash == *old_root && new_hash == *new_root
+    }
+}
@@ -0,0 +1,248 @@
+//! This module hashes and verifies passwords.
+//!
+//! Never store passwords hashed with the general purpose functions of the `hash` module: they
+//! are designed to be fast, which makes guessing passwords fast too. The functions here use
+//! slow, salted password hashing algorithms whose cost can be tuned:
+//! 1. Argon2id (RFC 9106), the winner of the Password Hashing Competition, and the default.
+//! 2. scrypt (RFC 7914).
+//! 3. PBKDF2 (RFC 8018) with HMAC-SHA256 or HMAC-SHA512, where FIPS compliance is required.
+//! 4. bcrypt, for compatibility with existing systems. It only uses the first 72 bytes of a
+//!    password, counting a terminating NUL, so passwords longer than 71 bytes are rejected
+//!    instead of silently truncated.
+//!
+//! A random salt is generated for every hash. The hashes are encoded as PHC strings (such as
+//! `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`) which carry the algorithm, the parameters
+//! and the salt, so `verify_password` needs nothing else. bcrypt hashes use their usual
+//! `$2b$<cost>$...` form.
+//!
+//! ```
+//! use estahr::password::{self, PasswordParams};
+//!
+//! let params = PasswordParams::Argon2id { memory_kib: 8 * 1024, iterations: 1, parallelism: 1 };
+//! let hash = password::hash_password("correct horse battery staple", &params).unwrap();
+//! assert!(password::verify_password("correct horse battery staple", &hash).unwrap());
+//! assert!(!password::verify_password("Tr0ub4dor&3", &hash).unwrap());
+//!
+//! // After raising the cost, rehash the password on the next successful login.
+//! assert!(password::needs_rehash(&hash, &PasswordParams::argon2id()).unwrap());
+//! ```
+
+use std::{error, fmt};
+use std::convert::TryFrom;
+
+use argon2::Argon2;
+use password_hash::rand_core::OsRng;
+use password_hash::{PasswordHash, PasswordHasher, SaltString};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+
+/// The error returned when a password cannot be hashed, or a hash cannot be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordError {
+    /// A cost parameter is out of the range of the algorithm.
+    InvalidParameter(String),
+    /// The hash is not a valid PHC string or bcrypt hash.
+    InvalidHash(String),
+    /// The hash uses an algorithm that is not supported.
+    UnsupportedAlgorithm(String),
+    /// The password is longer than the 71 bytes bcrypt can use.
+    PasswordTooLong,
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PasswordError::InvalidParameter(reason) => write!(f, "invalid password hashing parameter: {}", reason),
+            PasswordError::InvalidHash(reason) => write!(f, "invalid password hash: {}", reason),
+            PasswordError::UnsupportedAlgorithm(name) => write!(f, "unsupported password hashing algorithm: {}", name),
+            PasswordError::PasswordTooLong => write!(f, "the password is longer than 71 bytes, the limit of bcrypt"),
+        }
+    }
+}
+
+impl error::Error for PasswordError {}
+
+/// A password hashing algorithm with its cost parameters.
+///
+/// The constructors give the minimum costs recommended by OWASP at the time of writing.
+/// Raise them as far as the login latency allows, and check stored hashes with
+/// `needs_rehash` to upgrade them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PasswordParams {
+    /// Argon2id, version 1.3.
+    Argon2id {
+        /// The memory used, in KiB.
+        memory_kib: u32,
+        /// The number of passes over the memory.
+        iterations: u32,
+        /// The number of lanes.
+        parallelism: u32,
+    },
+    /// scrypt.
+    Scrypt {
+        /// The base 2 logarithm of the CPU and memory cost `N`.
+        log_n: u8,
+        /// The block size.
+        r: u32,
+        /// The parallelization.
+        p: u32,
+    },
+    /// PBKDF2 with HMAC-SHA256, giving a 32 byte hash.
+    Pbkdf2Sha256 {
+        /// The number of iterations.
+        rounds: u32,
+    },
+    /// PBKDF2 with HMAC-SHA512, giving a 64 byte hash.
+    Pbkdf2Sha512 {
+        /// The number of iterations.
+        rounds: u32,
+    },
+    /// bcrypt, version 2b.
+    Bcrypt {
+        /// The base 2 logarithm of the number of iterations, from 4 to 31.
+        cost: u32,
+    },
+}
+
+impl Default for PasswordParams {
+    fn default() -> Self {
+        PasswordParams::argon2id()
+    }
+}
+
+impl PasswordParams {
+    /// Argon2id with 19 MiB of memory, 2 iterations and 1 lane.
+    pub fn argon2id() -> Self {
+        PasswordParams::Argon2id { memory_kib: 19 * 1024, iterations: 2, parallelism: 1 }
+    }
+
+    /// scrypt with `N = 2^17`, `r = 8` and `p = 1` (128 MiB of memory).
+    pub fn scrypt() -> Self {
+        PasswordParams::Scrypt { log_n: 17, r: 8, p: 1 }
+    }
+
+    /// PBKDF2-HMAC-SHA256 with 600,000 iterations.
+    pub fn pbkdf2_sha256() -> Self {
+        PasswordParams::Pbkdf2Sha256 { rounds: 600_000 }
+    }
+
+    /// PBKDF2-HMAC-SHA512 with 210,000 iterations.
+    pub fn pbkdf2_sha512() -> Self {
+        PasswordParams::Pbkdf2Sha512 { rounds: 210_000 }
+    }
+
+    /// bcrypt with a cost of 12.
+    pub fn bcrypt() -> Self {
+        PasswordParams::Bcrypt { cost: 12 }
+    }
+
+    /// Reads the algorithm and parameters of a hash produced by `hash_password`.
+    pub fn from_hash(hash: &str) -> Result<PasswordParams, PasswordError> {
+        if hash.starts_with("$2") {
+            let parts: bcrypt::HashParts = hash.parse().map_err(|e: bcrypt::BcryptError| {
+                PasswordError::InvalidHash(e.to_string())
+            })?;
+            return Ok(PasswordParams::Bcrypt { cost: parts.get_cost() });
+        }
+        let parsed = PasswordHash::new(hash).map_err(|e| PasswordError::InvalidHash(e.to_string()))?;
+        let invalid = |e: password_hash::Error| PasswordError::InvalidHash(e.to_string());
+        match parsed.algorithm.as_str() {
+            "argon2id" => {
+                let params = argon2::Params::try_from(&parsed).map_err(invalid)?;
+                Ok(PasswordParams::Argon2id {
+                    memory_kib: params.m_cost(),
+                    iterations: params.t_cost(),
+                    parallelism: params.p_cost(),
+                })
+            }
+            "scrypt" => {
+                let params = scrypt::Params::try_from(&parsed).map_err(invalid)?;
+                Ok(PasswordParams::Scrypt { log_n: params.log_n(), r: params.r(), p: params.p() })
+            }
+            "pbkdf2-sha256" => {
+                let params = pbkdf2::Params::try_from(&parsed).map_err(invalid)?;
+                Ok(PasswordParams::Pbkdf2Sha256 { rounds: params.rounds })
+            }
+            "pbkdf2-sha512" => {
+                let params = pbkdf2::Params::try_from(&parsed).map_err(invalid)?;
+                Ok(PasswordParams::Pbkdf2Sha512 { rounds: params.rounds })
+            }
+            other => Err(PasswordError::UnsupportedAlgorithm(other.to_string())),
+        }
+    }
+}
+
+/// Hashes a password with a new random salt, and returns the PHC string (or bcrypt hash) to
+/// store.
+///
+/// Returns an error if the parameters are out of range for the algorithm, or if the password
+/// is too long for bcrypt.
+pub fn hash_password(password: &str, params: &PasswordParams) -> Result<String, PasswordError> {
+    let password = password.as_bytes();
+    let salt = SaltString::generate(&mut OsRng);
+    let invalid = |e: password_hash::Error| PasswordError::InvalidParameter(e.to_string());
+    if let PasswordParams::Pbkdf2Sha256 { rounds: 0 } | PasswordParams::Pbkdf2Sha512 { rounds: 0 } = params {
+        return Err(PasswordError::InvalidParameter("PBKDF2 needs at least one round".to_string()));
+    }
+    let hash = match *params {
+        PasswordParams::Argon2id { memory_kib, iterations, parallelism } => {
+            let argon2_params = argon2::Params::new(memory_kib, iterations, parallelism, None)
+                .map_err(|e| PasswordError::InvalidParameter(e.to_string()))?;
+            Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, argon2_params)
+                .hash_password(password, &salt)
+                .map_err(invalid)?
+        }
+        PasswordParams::Scrypt { log_n, r, p } => {
+            let scrypt_params = scrypt::Params::new(log_n, r, p, scrypt::Params::RECOMMENDED_LEN)
+                .map_err(|e| PasswordError::InvalidParameter(e.to_string()))?;
+            Scrypt.hash_password_customized(password, None, None, scrypt_params, &salt).map_err(invalid)?
+        }
+        PasswordParams::Pbkdf2Sha256 { rounds } => {
+            let pbkdf2_params = pbkdf2::Params { rounds, output_length: 32 };
+            Pbkdf2.hash_password_customized(password, Some(pbkdf2::Algorithm::Pbkdf2Sha256.ident()), None,
+                                            pbkdf2_params, &salt).map_err(invalid)?
+        }
+        PasswordParams::Pbkdf2Sha512 { rounds } => {
+            let pbkdf2_params = pbkdf2::Params { rounds, output_length: 64 };
+            Pbkdf2.hash_password_customized(password, Some(pbkdf2::Algorithm::Pbkdf2Sha512.ident()), None,
+                                            pbkdf2_params, &salt).map_err(invalid)?
+        }
+        PasswordParams::Bcrypt { cost } => {
+            return bcrypt::non_truncating_hash(password, cost).map_err(|e| match e {
+                bcrypt::BcryptError::Truncation(_) => PasswordError::PasswordTooLong,
+                e => PasswordError::InvalidParameter(e.to_string()),
+            });
+        }
+    };
+    Ok(hash.to_string())
+}
+
+/// Checks a password against a hash produced by `hash_password`.
+///
+/// Returns `Ok(false)` if the password does not match, and an error if the hash cannot be
+/// read. The comparison runs in constant time.
+pub fn verify_password(password: &str, hash: &str) -> Result<bool, PasswordError> {
+    // Checking the hash first rejects unsupported algorithms, such as PBKDF2-HMAC-SHA1.
+    PasswordParams::from_hash(hash)?;
+    if hash.starts_with("$2") {
+        return match bcrypt::non_truncating_verify(password, hash) {
+            Ok(matches) => Ok(matches),
+            // A password too long for bcrypt cannot have produced the hash.
+            Err(bcrypt::BcryptError::Truncation(_)) => Ok(false),
+            Err(e) => Err(PasswordError::InvalidHash(e.to_string())),
+        };
+    }
+    let parsed = PasswordHash::new(hash).map_err(|e| PasswordError::InvalidHash(e.to_string()))?;
+    match parsed.verify_password(&[&Argon2::default(), &Scrypt, &Pbkdf2], password) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(PasswordError::InvalidHash(e.to_string())),
+    }
+}
+
+/// Returns true if the hash was not produced with the given algorithm and parameters, so
+/// that the password should be hashed again (after a successful `verify_password`, when it
+/// is at hand).
+pub fn needs_rehash(hash: &str, params: &PasswordParams) -> Result<bool, PasswordError> {
+    Ok(PasswordParams::from_hash(hash)? != *params)
+}
@@ -0,0 +1,446 @@
+//! This module assigns keys to nodes (shards) deterministically, with the consistent hashing
+//! schemes that move few keys when nodes are added or removed.
+//!
+//! The schemes included are:
+//! 1. `HashRing`, a consistent hash ring (Karger et al.) with virtual nodes.
+//! 2. `Rendezvous`, highest random weight hashing (Thaler and Ravishankar).
+//! 3. `JumpHash`, jump consistent hash (Lamping and Veach), for numbered shards that are only
+//!    added or removed at the end.
+//! 4. `Maglev`, the lookup table of Google's Maglev load balancer (Eisenbud et al.), which
+//!    spreads keys almost perfectly evenly.
+//!
+//! All of them hash keys and node names with a chosen `HashAlgorithm`, and implement the
+//! `Sharder` trait. `moved_keys` reports how many keys of a sample are assigned to a different
+//! node by two sharders, such as the same scheme before and after a node is added.
+//!
+//! ```
+//! use estahr::hash::HashAlgorithm;
+//! use estahr::sharding::{self, HashRing, Sharder};
+//!
+//! let mut ring = HashRing::new(HashAlgorithm::BLAKE3, 100);
+//! ring.add_node("cache-a");
+//! ring.add_node("cache-b");
+//! let before = ring.clone();
+//! ring.add_node("cache-c");
+//!
+//! let keys: Vec<String> = (0..1000).map(|i| format!("user:{}", i)).collect();
+//! let movement = sharding::moved_keys(&before, &ring, &keys);
+//! // Only the keys taken over by the new node move, about a third of them.
+//! assert!(movement.fraction() > 0.2 && movement.fraction() < 0.45);
+//! assert!(keys.iter().all(|key| {
+//!     before.node_for(key) == ring.node_for(key) || ring.node_for(key) == Some("cache-c")
+//! }));
+//! ```
+//!
+//! The percentage helpers, `percentage_bucket` and `in_percentage`, place keys in a stable
+//! position from 0 to 100 per experiment, for A/B tests and gradual rollouts.
+
+use std::collections::BTreeMap;
+
+use crate::hash::{hash_bytes, HashAlgorithm};
+
+/// Hashes the input to 64 bits: the first 8 bytes of its digest, big endian.
+fn hash64(algorithm: HashAlgorithm, input: &[u8]) -> u64 {
+    let digest = hash_bytes(algorithm, input);
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest.as_bytes()[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+/// The SplitMix64 finalizer, to combine a key hash with a node hash.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Maps a 64 bit hash uniformly to `0..n`.
+fn reduce(hash: u64, n: usize) -> usize {
+    ((u128::from(hash) * n as u128) >> 64) as usize
+}
+
+/// A scheme assigning keys to nodes.
+pub trait Sharder {
+    /// The node the key is assigned to, or `None` if there are no nodes.
+    fn node_for<K: AsRef<[u8]> + ?Sized>(&self, key: &K) -> Option<&str>;
+}
+
+/// How many keys are assigned to a different node, returned by `moved_keys`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyMovement {
+    /// The number of keys compared.
+    pub total: usize,
+    /// The number of keys assigned to a different node (or to a node by only one of the
+    /// sharders).
+    pub moved: usize,
+}
+
+impl KeyMovement {
+    /// The fraction of the keys that moved, from 0 to 1. 0 if no keys were compared.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.moved as f64 / self.total as f64
+    }
+}
+
+/// Counts the keys that `before` and `after` assign to different nodes.
+///
+/// With a consistent scheme, adding one node to `n` moves about `1 / (n + 1)` of the keys,
+/// all of them to the new node, and removing one moves only the keys it had.
+pub fn moved_keys<A, B, I>(before: &A, after: &B, keys: I) -> KeyMovement
+    where A: Sharder, B: Sharder, I: IntoIterator, I::Item: AsRef<[u8]> {
+    let mut movement = KeyMovement { total: 0, moved: 0 };
+    for key in keys {
+        let key = key.as_ref();
+        movement.total += 1;
+        if before.node_for(key) != after.node_for(key) {
+            movement.moved += 1;
+        }
+    }
+    movement
+}
+
+/// A consistent hash ring with virtual nodes.
+///
+/// Every node is placed at `vnodes` points of a 64 bit ring, and a key is assigned to the
+/// node at the first point after its hash. More virtual nodes spread the keys more evenly:
+/// with 100 to 200 per node, the busiest node gets about 10% more keys than the average.
+#[derive(Debug, Clone)]
+pub struct HashRing {
+    algorithm: HashAlgorithm,
+    vnodes: usize,
+    ring: BTreeMap<u64, String>,
+    nodes: Vec<String>,
+}
+
+impl HashRing {
+    /// Creates an empty ring, placing every node at `vnodes` points (at least 1).
+    pub fn new(algorithm: HashAlgorithm, vnodes: usize) -> HashRing {
+        HashRing { algorithm, vnodes: vnodes.max(1), ring: BTreeMap::new(), nodes: Vec::new() }
+    }
+
+    fn points<'a>(&'a self, node: &'a str) -> impl Iterator<Item=u64> + 'a {
+        (0..self.vnodes).map(move |i| hash64(self.algorithm, format!("{}#{}", node, i).as_bytes()))
+    }
+
+    /// Adds a node to the ring. Returns false if it was already there.
+    pub fn add_node(&mut self, node: &str) -> bool {
+        if self.nodes.iter().any(|existing| existing == node) {
+            return false;
+        }
+        let points: Vec<u64> = self.points(node).collect();
+        for point in points {
+            // On the rare collision of two points, the smallest node name keeps it, so that
+            // the ring does not depend on the order the nodes were added in.
+            let owner = self.ring.entry(point).or_insert_with(|| node.to_string());
+            if node < owner.as_str() {
+                *owner = node.to_string();
+            }
+        }
+        self.nodes.push(node.to_string());
+        true
+    }
+
+    /// Removes a node from the ring. Returns false if it was not there.
+    pub fn remove_node(&mut self, node: &str) -> bool {
+        match self.nodes.iter().position(|existing| existing == node) {
+            Some(index) => {
+                self.nodes.remove(index);
+                self.ring.retain(|_, owner| owner != node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The nodes, in the order they were added.
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+}
+
+impl Sharder for HashRing {
+    fn node_for<K: AsRef<[u8]> + ?Sized>(&self, key: &K) -> Option<&str> {
+        let hash = hash64(self.algorithm, key.as_ref());
+        self.ring.range(hash..).next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, node)| node.as_str())
+    }
+}
+
+/// Rendezvous, or highest random weight, hashing.
+///
+/// Every node gets a score for the key, and the key is assigned to the node with the highest
+/// score. The keys are spread evenly without virtual nodes, at the cost of a lookup linear in
+/// the number of nodes.
+#[derive(Debug, Clone)]
+pub struct Rendezvous {
+    algorithm: HashAlgorithm,
+    nodes: Vec<(String, u64)>,
+}
+
+impl Rendezvous {
+    /// Creates an empty set of nodes.
+    pub fn new(algorithm: HashAlgorithm) -> Rendezvous {
+        Rendezvous { algorithm, nodes: Vec::new() }
+    }
+
+    /// Adds a node. Returns false if it was already there.
+    pub fn add_node(&mut self, node: &str) -> bool {
+        if self.nodes.iter().any(|(existing, _)| existing == node) {
+            return false;
+        }
+        self.nodes.push((node.to_string(), hash64(self.algorithm, node.as_bytes())));
+        true
+    }
+
+    /// Removes a node. Returns false if it was not there.
+    pub fn remove_node(&mut self, node: &str) -> bool {
+        let len = self.nodes.len();
+        self.nodes.retain(|(existing, _)| existing != node);
+        self.nodes.len() != len
+    }
+
+    /// The nodes, in the order they were added.
+    pub fn nodes(&self) -> Vec<&str> {
+        self.nodes.iter().map(|(node, _)| node.as_str()).collect()
+    }
+
+    /// All the nodes, from the highest score for the key to the lowest.
+    ///
+    /// The first node is the one the key is assigned to, and the next ones are where its
+    /// replicas (or fallbacks) go.
+    pub fn ranked_nodes<K: AsRef<[u8]> + ?Sized>(&self, key: &K) -> Vec<&str> {
+        let hash = hash64(self.algorithm, key.as_ref());
+        let mut scored: Vec<(u64, &str)> = self.nodes.iter()
+            .map(|(node, node_hash)| (mix(hash ^ node_hash), node.as_str()))
+            .collect();
+        scored.sort_unstable_by(|a, b| b.cmp(a));
+        scored.into_iter().map(|(_, node)| node).collect()
+    }
+}
+
+impl Sharder for Rendezvous {
+    fn node_for<K: AsRef<[u8]> + ?Sized>(&self, key: &K) -> Option<&str> {
+        let hash = hash64(self.algorithm, key.as_ref());
+        self.nodes.iter()
+            .max_by_key(|(node, node_hash)| (mix(hash ^ node_hash), node.as_str()))
+            .map(|(node, _)| node.as_str())
+    }
+}
+
+/// Computes the jump consistent hash of a 64 bit key hash: its bucket in `0..buckets`.
+///
+/// Going from `n` to `n + 1` buckets moves `1 / (n + 1)` of the keys, all to the new bucket.
+/// Returns 0 if `buckets` is 0.
+pub fn jump_hash(mut key: u64, buckets: u32) -> u32 {
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+    while next < i64::from(buckets) {
+        bucket = next;
+        key = key.wrapping_mul(2_862_933_555_777_941_757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    bucket.max(0) as u32
+}
+
+/// Jump consistent hash over a list of nodes.
+///
+/// It needs no memory beyond the node names and spreads the keys evenly, but nodes can only
+/// be added or removed at the end of the list: removing any other node would move most of
+/// the keys.
+#[derive(Debug, Clone)]
+pub struct JumpHash {
+    algorithm: HashAlgorithm,
+    nodes: Vec<String>,
+}
+
+impl JumpHash {
+    /// Creates an empty list of nodes.
+    pub fn new(algorithm: HashAlgorithm) -> JumpHash {
+        JumpHash { algorithm, nodes: Vec::new() }
+    }
+
+    /// Appends a node to the list.
+    pub fn push_node(&mut self, node: &str) {
+        self.nodes.push(node.to_string());
+    }
+
+    /// Removes the last node of the list, and returns it.
+    pub fn pop_node(&mut self) -> Option<String> {
+        self.nodes.pop()
+    }
+
+    /// The nodes, in order.
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+}
+
+impl Sharder for JumpHash {
+    fn node_for<K: AsRef<[u8]> + ?Sized>(&self, key: &K) -> Option<&str> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let bucket = jump_hash(hash64(self.algorithm, key.as_ref()), self.nodes.len() as u32);
+        Some(&self.nodes[bucket as usize])
+    }
+}
+
+/// A Maglev lookup table.
+///
+/// Every node fills the slots of a table in its own pseudo random order, taking turns, so
+/// that each node gets an almost equal share of the slots (they differ by at most one), and
+/// a key is assigned to the node of the slot its hash falls in. Adding or removing a node
+/// moves slightly more keys than the minimum. The table is rebuilt on every change, in
+/// `O(table_size)` time on average.
+#[derive(Debug, Clone)]
+pub struct Maglev {
+    algorithm: HashAlgorithm,
+    table_size: usize,
+    nodes: Vec<String>,
+    table: Vec<usize>,
+}
+
+impl Maglev {
+    /// The default table size, a prime suited to up to about 650 nodes.
+    pub const DEFAULT_TABLE_SIZE: usize = 65_537;
+
+    /// Creates an empty table of the default size.
+    pub fn new(algorithm: HashAlgorithm) -> Maglev {
+        Maglev::with_table_size(algorithm, Maglev::DEFAULT_TABLE_SIZE)
+    }
+
+    /// Creates an empty table of the given size.
+    ///
+    /// The size should be a prime at least 100 times the number of nodes, which keeps the
+    /// shares within 1% of each other. A size that is not a prime is rounded up to the next
+    /// one.
+    pub fn with_table_size(algorithm: HashAlgorithm, table_size: usize) -> Maglev {
+        let mut size = table_size.max(2);
+        while !is_prime(size) {
+            size += 1;
+        }
+        Maglev { algorithm, table_size: size, nodes: Vec::new(), table: Vec::new() }
+    }
+
+    /// The number of slots of the table.
+    pub fn table_size(&self) -> usize {
+        self.table_size
+    }
+
+    /// Adds a node and rebuilds the table. Returns false if it was already there.
+    pub fn add_node(&mut self, node: &str) -> bool {
+        if self.nodes.iter().any(|existing| existing == node) {
+            return false;
+        }
+        self.nodes.push(node.to_string());
+        self.rebuild();
+        true
+    }
+
+    /// Removes a node and rebuilds the table. Returns false if it was not there.
+    pub fn remove_node(&mut self, node: &str) -> bool {
+        match self.nodes.iter().position(|existing| existing == node) {
+            Some(index) => {
+                self.nodes.remove(index);
+                self.rebuild();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The nodes, sorted by name.
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    fn rebuild(&mut self) {
+        // The table only depends on the set of nodes, not on the order they were added in.
+        self.nodes.sort();
+        let m = self.table_size;
+        if self.nodes.is_empty() {
+            self.table.clear();
+            return;
+        }
+        let permutations: Vec<(usize, usize)> = self.nodes.iter().map(|node| {
+            let digest = hash_bytes(self.algorithm, node.as_bytes());
+            let mut offset = [0; 8];
+            let mut skip = [0; 8];
+            offset.copy_from_slice(&digest.as_bytes()[..8]);
+            skip.copy_from_slice(&digest.as_bytes()[8..16]);
+            (reduce(u64::from_be_bytes(offset), m), reduce(u64::from_be_bytes(skip), m - 1) + 1)
+        }).collect();
+
+        let mut next = vec![0; self.nodes.len()];
+        let mut table = vec![usize::MAX; m];
+        let mut filled = 0;
+        'fill: loop {
+            for (node, &(offset, skip)) in permutations.iter().enumerate() {
+                let mut slot = (offset + next[node] * skip) % m;
+                while table[slot] != usize::MAX {
+                    next[node] += 1;
+                    slot = (offset + next[node] * skip) % m;
+                }
+                table[slot] = node;
+                next[node] += 1;
+                filled += 1;
+                if filled == m {
+                    break 'fill;
+                }
+            }
+        }
+        self.table = table;
+    }
+}
+
+impl Sharder for Maglev {
+    fn node_for<K: AsRef<[u8]> + ?Sized>(&self, key: &K) -> Option<&str> {
+        if self.table.is_empty() {
+            return None;
+        }
+        let slot = reduce(hash64(self.algorithm, key.as_ref()), self.table_size);
+        Some(&self.nodes[self.table[slot]])
+    }
+}
+
+fn is_prime(n: usize) -> bool {
+    n >= 2 && (2..).take_while(|i| i * i <= n).all(|i| !n.is_multiple_of(i))
+}
+
+/// The position of the key in an experiment (or rollout), from 0 (inclusive) to 100
+/// (exclusive).
+///
+/// The position is a hash of the experiment name and the key, so a key always gets the same
+/// position in an experiment, and independent positions in different experiments.
+pub fn percentage_bucket<K: AsRef<[u8]> + ?Sized>(algorithm: HashAlgorithm, experiment: &str, key: &K) -> f64 {
+    let mut input = Vec::with_capacity(8 + experiment.len() + key.as_ref().len());
+    input.extend_from_slice(&(experiment.len() as u64).to_le_bytes());
+    input.extend_from_slice(experiment.as_bytes());
+    input.extend_from_slice(key.as_ref());
+    // The top 53 bits give every representable position the same probability.
+    (hash64(algorithm, &input) >> 11) as f64 / (1u64 << 53) as f64 * 100.0
+}
+
+/// Returns true if the key is in the first `percentage` percent of the experiment.
+///
+/// Raising the percentage only adds keys: a key that was in keeps being in, which is what a
+/// gradual rollout needs. For an A/B split, compare `percentage_bucket` with the cumulative
+/// share of every variant.
+///
+/// ```
+/// use estahr::hash::HashAlgorithm;
+/// use estahr::sharding;
+///
+/// let in_beta = sharding::in_percentage(HashAlgorithm::SHA2_256, "new-checkout", "user:42", 10.0);
+/// assert_eq!(in_beta, sharding::in_percentage(HashAlgorithm::SHA2_256, "new-checkout", "user:42", 10.0));
+/// assert!(!sharding::in_percentage(HashAlgorithm::SHA2_256, "new-checkout", "user:42", 0.0));
+/// assert!(sharding::in_percentage(HashAlgorithm::SHA2_256, "new-checkout", "user:42", 100.0));
+/// ```
+pub fn in_percentage<K: AsRef<[u8]> + ?Sized>(algorithm: HashAlgorithm, experiment: &str, key: &K,
+                                              percentage: f64) -> bool {
+    percentage_bucket(algorithm, experiment, key) < percentage
+}
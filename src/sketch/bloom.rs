@@ -0,0 +1,141 @@
+use std::f64::consts::LN_2;
+
+use super::{check_compatible, item_hash, Decoder, Encoder, SketchError};
+
+const MAGIC: &[u8; 4] = b"EBLM";
+
+/// A Bloom filter: a set that may answer that it contains an item it does not, but never
+/// that it does not contain an item it does.
+///
+/// Built with `BloomFilter::new(n, p)`, the filter uses about `-n ln(p) / ln(2)^2` bits
+/// (9.6 bits per item for 1%) and, as long as at most `n` items are inserted, answers
+/// `contains` with a false positive rate of at most about `p`. Inserting more items raises
+/// the rate, which `estimated_false_positive_rate` tracks.
+///
+/// ```
+/// use estahr::sketch::BloomFilter;
+///
+/// let mut filter = BloomFilter::new(1000, 0.01).unwrap();
+/// filter.insert("apple");
+/// assert!(filter.contains("apple"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    seed: u64,
+}
+
+impl BloomFilter {
+    /// Creates a filter sized for `expected_items` items at the given false positive rate.
+    ///
+    /// Returns an error if `expected_items` is 0 or the rate is not strictly between 0 and 1.
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Result<BloomFilter, SketchError> {
+        if expected_items == 0 {
+            return Err(SketchError::InvalidParameter("expected_items must be positive".to_string()));
+        }
+        if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+            return Err(SketchError::InvalidParameter(
+                format!("false_positive_rate must be between 0 and 1, got {}", false_positive_rate)));
+        }
+        let num_bits = (-(expected_items as f64) * false_positive_rate.ln() / (LN_2 * LN_2)).ceil();
+        let num_hashes = (num_bits / expected_items as f64 * LN_2).round().max(1.0);
+        BloomFilter::with_params(num_bits as u64, num_hashes as u32, 0)
+    }
+
+    /// Creates a filter with exactly `num_bits` bits and `num_hashes` hash functions.
+    ///
+    /// Filters with different seeds hash the items independently, and cannot be merged.
+    pub fn with_params(num_bits: u64, num_hashes: u32, seed: u64) -> Result<BloomFilter, SketchError> {
+        if num_bits == 0 || num_hashes == 0 {
+            return Err(SketchError::InvalidParameter("num_bits and num_hashes must be positive".to_string()));
+        }
+        let words = num_bits.div_ceil(64) as usize;
+        Ok(BloomFilter { bits: vec![0; words], num_bits, num_hashes, seed })
+    }
+
+    /// The number of bits of the filter.
+    pub fn num_bits(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// The number of hash functions, which is the number of bits set per item.
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// The bit positions of an item, by double hashing (Kirsch and Mitzenmacher).
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item=u64> {
+        let (h1, h2) = item_hash(item, self.seed);
+        let num_bits = self.num_bits;
+        (0..u64::from(self.num_hashes)).map(move |i| h1.wrapping_add(i.wrapping_mul(h2 | 1)) % num_bits)
+    }
+
+    /// Adds an item to the filter.
+    pub fn insert<T: AsRef<[u8]> + ?Sized>(&mut self, item: &T) {
+        for position in self.positions(item.as_ref()).collect::<Vec<_>>() {
+            self.bits[(position / 64) as usize] |= 1 << (position % 64);
+        }
+    }
+
+    /// Returns false if the item was never inserted, and true if it probably was.
+    pub fn contains<T: AsRef<[u8]> + ?Sized>(&self, item: &T) -> bool {
+        self.positions(item.as_ref()).all(|position| self.bits[(position / 64) as usize] & (1 << (position % 64)) != 0)
+    }
+
+    /// Estimates the current false positive rate from the fraction of bits that are set.
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        let ones: u64 = self.bits.iter().map(|word| u64::from(word.count_ones())).sum();
+        (ones as f64 / self.num_bits as f64).powi(self.num_hashes as i32)
+    }
+
+    /// Removes all the items.
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|word| *word = 0);
+    }
+
+    /// Adds all the items of the other filter to this one.
+    ///
+    /// Both filters must have the same number of bits, hash functions and seed.
+    pub fn merge(&mut self, other: &BloomFilter) -> Result<(), SketchError> {
+        check_compatible("num_bits", self.num_bits, other.num_bits)?;
+        check_compatible("num_hashes", self.num_hashes, other.num_hashes)?;
+        check_compatible("seeds", self.seed, other.seed)?;
+        for (word, other_word) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *word |= other_word;
+        }
+        Ok(())
+    }
+
+    /// Encodes the filter: the header, the number of bits, hashes and the seed, then the bits
+    /// as 64 bit words.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, 20 + 8 * self.bits.len());
+        encoder.u64(self.num_bits);
+        encoder.u64(u64::from(self.num_hashes));
+        encoder.u64(self.seed);
+        for &word in self.bits.iter() {
+            encoder.u64(word);
+        }
+        encoder.finish()
+    }
+
+    /// Decodes a filter encoded with `to_bytes`.
+    pub fn from_bytes(input: &[u8]) -> Result<BloomFilter, SketchError> {
+        let mut decoder = Decoder::new(MAGIC, input)?;
+        let num_bits = decoder.u64()?;
+        let num_hashes = decoder.u64()?;
+        let seed = decoder.u64()?;
+        if num_hashes > u64::from(u32::MAX) || num_bits.div_ceil(64) * 8 != decoder.input.len() as u64 {
+            return Err(SketchError::InvalidFormat("inconsistent bloom filter size".to_string()));
+        }
+        let mut filter = BloomFilter::with_params(num_bits, num_hashes as u32, seed)
+            .map_err(|e| SketchError::InvalidFormat(e.to_string()))?;
+        for word in filter.bits.iter_mut() {
+            *word = decoder.u64()?;
+        }
+        decoder.finish()?;
+        Ok(filter)
+    }
+}
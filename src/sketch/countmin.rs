@@ -0,0 +1,146 @@
+use std::f64::consts::E;
+
+use super::{check_compatible, item_hash, Decoder, Encoder, SketchError};
+
+const MAGIC: &[u8; 4] = b"ECMS";
+
+/// A Count-Min sketch (Cormode and Muthukrishnan, 2005), for the approximate count of every
+/// item in a stream.
+///
+/// The estimate of an item is never below its true count. Built with
+/// `CountMinSketch::new(epsilon, delta)`, it is more than `epsilon * total` above the true
+/// count with probability at most `delta`, where `total` is the sum of all the counts added.
+/// The sketch uses `ceil(e / epsilon) * ceil(ln(1 / delta))` counters.
+///
+/// ```
+/// use estahr::sketch::CountMinSketch;
+///
+/// let mut sketch = CountMinSketch::new(0.001, 0.01).unwrap();
+/// sketch.add("apple", 3);
+/// sketch.add("pear", 1);
+/// assert!(sketch.estimate("apple") >= 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountMinSketch {
+    counters: Vec<u64>,
+    width: usize,
+    depth: usize,
+    total: u64,
+    seed: u64,
+}
+
+impl CountMinSketch {
+    /// Creates a sketch whose estimates are within `epsilon * total` of the true count with
+    /// probability `1 - delta`.
+    ///
+    /// Both parameters must be strictly between 0 and 1.
+    pub fn new(epsilon: f64, delta: f64) -> Result<CountMinSketch, SketchError> {
+        if !(epsilon > 0.0 && epsilon < 1.0 && delta > 0.0 && delta < 1.0) {
+            return Err(SketchError::InvalidParameter(
+                format!("epsilon ({}) and delta ({}) must be between 0 and 1", epsilon, delta)));
+        }
+        let width = (E / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil().max(1.0) as usize;
+        CountMinSketch::with_dimensions(width, depth, 0)
+    }
+
+    /// Creates a sketch with `depth` rows of `width` counters.
+    ///
+    /// Sketches with different seeds hash the items independently, and cannot be merged.
+    pub fn with_dimensions(width: usize, depth: usize, seed: u64) -> Result<CountMinSketch, SketchError> {
+        let size = width.checked_mul(depth)
+            .filter(|&size| size > 0)
+            .ok_or_else(|| SketchError::InvalidParameter(format!("invalid dimensions {}x{}", width, depth)))?;
+        Ok(CountMinSketch { counters: vec![0; size], width, depth, total: 0, seed })
+    }
+
+    /// The number of counters per row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The number of rows.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The sum of all the counts added.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// The counter of the item in each row, by double hashing.
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item=usize> {
+        let (h1, h2) = item_hash(item, self.seed);
+        let width = self.width as u64;
+        (0..self.depth).map(move |row| {
+            row * width as usize + (h1.wrapping_add((row as u64).wrapping_mul(h2 | 1)) % width) as usize
+        })
+    }
+
+    /// Adds `count` to the count of the item. The counters saturate instead of overflowing.
+    pub fn add<T: AsRef<[u8]> + ?Sized>(&mut self, item: &T, count: u64) {
+        for position in self.positions(item.as_ref()).collect::<Vec<_>>() {
+            self.counters[position] = self.counters[position].saturating_add(count);
+        }
+        self.total = self.total.saturating_add(count);
+    }
+
+    /// Estimates the count of the item. The estimate is never below the true count.
+    pub fn estimate<T: AsRef<[u8]> + ?Sized>(&self, item: &T) -> u64 {
+        self.positions(item.as_ref()).map(|position| self.counters[position]).min().unwrap_or(0)
+    }
+
+    /// Resets all the counts to 0.
+    pub fn clear(&mut self) {
+        self.counters.iter_mut().for_each(|counter| *counter = 0);
+        self.total = 0;
+    }
+
+    /// Adds all the counts of the other sketch to this one.
+    ///
+    /// Both sketches must have the same dimensions and seed.
+    pub fn merge(&mut self, other: &CountMinSketch) -> Result<(), SketchError> {
+        check_compatible("dimensions", (self.width, self.depth), (other.width, other.depth))?;
+        check_compatible("seeds", self.seed, other.seed)?;
+        for (counter, other_counter) in self.counters.iter_mut().zip(other.counters.iter()) {
+            *counter = counter.saturating_add(*other_counter);
+        }
+        self.total = self.total.saturating_add(other.total);
+        Ok(())
+    }
+
+    /// Encodes the sketch: the header, the width, depth, seed and total, then the counters
+    /// row by row.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, 32 + 8 * self.counters.len());
+        encoder.u64(self.width as u64);
+        encoder.u64(self.depth as u64);
+        encoder.u64(self.seed);
+        encoder.u64(self.total);
+        for &counter in self.counters.iter() {
+            encoder.u64(counter);
+        }
+        encoder.finish()
+    }
+
+    /// Decodes a sketch encoded with `to_bytes`.
+    pub fn from_bytes(input: &[u8]) -> Result<CountMinSketch, SketchError> {
+        let mut decoder = Decoder::new(MAGIC, input)?;
+        let width = decoder.u64()?;
+        let depth = decoder.u64()?;
+        let seed = decoder.u64()?;
+        let total = decoder.u64()?;
+        if width.checked_mul(depth).and_then(|size| size.checked_mul(8)) != Some(decoder.input.len() as u64) {
+            return Err(SketchError::InvalidFormat("inconsistent count-min sketch size".to_string()));
+        }
+        let mut sketch = CountMinSketch::with_dimensions(width as usize, depth as usize, seed)
+            .map_err(|e| SketchError::InvalidFormat(e.to_string()))?;
+        for counter in sketch.counters.iter_mut() {
+            *counter = decoder.u64()?;
+        }
+        sketch.total = total;
+        decoder.finish()?;
+        Ok(sketch)
+    }
+}
@@ -0,0 +1,214 @@
+use super::{check_compatible, item_hash, Decoder, Encoder, SketchError};
+
+const MAGIC: &[u8; 4] = b"ECKF";
+const BUCKET_SIZE: usize = 4;
+const MAX_KICKS: usize = 500;
+
+/// A cuckoo filter (Fan et al., 2014): a set with false positives like a Bloom filter, that
+/// also supports removing items.
+///
+/// Each item is stored as a 16 bit fingerprint in one of two buckets of 4 slots. An item that
+/// was never inserted is reported as present with probability at most `8 / 2^16`, about
+/// 0.012%, whatever the load. Inserts start failing with `SketchError::Full` at around 95%
+/// of the capacity.
+///
+/// Only remove items that were inserted: removing any other item may remove the fingerprint
+/// of an item that was, and cause a false negative.
+///
+/// ```
+/// use estahr::sketch::CuckooFilter;
+///
+/// let mut filter = CuckooFilter::new(1000).unwrap();
+/// filter.insert("apple").unwrap();
+/// assert!(filter.contains("apple"));
+/// assert!(filter.remove("apple"));
+/// assert!(!filter.contains("apple"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CuckooFilter {
+    slots: Vec<u16>,
+    len: u64,
+    seed: u64,
+}
+
+impl CuckooFilter {
+    /// Creates a filter that can hold at least `capacity` items.
+    pub fn new(capacity: usize) -> Result<CuckooFilter, SketchError> {
+        CuckooFilter::with_seed(capacity, 0)
+    }
+
+    /// Creates a filter that can hold at least `capacity` items, hashing them with the given
+    /// seed. Filters with different seeds cannot be merged.
+    pub fn with_seed(capacity: usize, seed: u64) -> Result<CuckooFilter, SketchError> {
+        if capacity == 0 {
+            return Err(SketchError::InvalidParameter("capacity must be positive".to_string()));
+        }
+        // Sized for a load of at most 95%.
+        let buckets = (capacity as f64 / 0.95 / BUCKET_SIZE as f64).ceil() as usize;
+        let buckets = buckets.checked_next_power_of_two()
+            .filter(|&buckets| buckets.checked_mul(BUCKET_SIZE).is_some())
+            .ok_or_else(|| SketchError::InvalidParameter(format!("capacity {} is too large", capacity)))?;
+        Ok(CuckooFilter { slots: vec![0; buckets * BUCKET_SIZE], len: 0, seed })
+    }
+
+    /// The number of items the filter holds.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the filter holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of slots, which bounds the number of items the filter can hold.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn num_buckets(&self) -> usize {
+        self.slots.len() / BUCKET_SIZE
+    }
+
+    /// The fingerprint of an item (never 0, which marks an empty slot) and its first bucket.
+    fn fingerprint_and_index(&self, item: &[u8]) -> (u16, usize) {
+        let (h1, h2) = item_hash(item, self.seed);
+        let fingerprint = (h2 as u16).max(1);
+        (fingerprint, h1 as usize & (self.num_buckets() - 1))
+    }
+
+    /// The other bucket of a fingerprint. Applying it twice gives the first bucket back.
+    fn alt_index(&self, index: usize, fingerprint: u16) -> usize {
+        let (hash, _) = item_hash(&fingerprint.to_le_bytes(), self.seed);
+        (index ^ hash as usize) & (self.num_buckets() - 1)
+    }
+
+    fn bucket(&self, index: usize) -> &[u16] {
+        &self.slots[index * BUCKET_SIZE..(index + 1) * BUCKET_SIZE]
+    }
+
+    fn bucket_mut(&mut self, index: usize) -> &mut [u16] {
+        &mut self.slots[index * BUCKET_SIZE..(index + 1) * BUCKET_SIZE]
+    }
+
+    fn try_put(&mut self, index: usize, fingerprint: u16) -> bool {
+        match self.bucket_mut(index).iter_mut().find(|slot| **slot == 0) {
+            Some(slot) => {
+                *slot = fingerprint;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stores a fingerprint, relocating others if both of its buckets are full.
+    fn put(&mut self, index: usize, fingerprint: u16) -> Result<(), SketchError> {
+        let alt = self.alt_index(index, fingerprint);
+        if self.try_put(index, fingerprint) || self.try_put(alt, fingerprint) {
+            self.len += 1;
+            return Ok(());
+        }
+        // Kick fingerprints out to their other bucket, remembering the swaps so that they can
+        // be undone if no free slot is found and the filter is left unchanged.
+        let mut swaps = Vec::new();
+        let mut index = alt;
+        let mut fingerprint = fingerprint;
+        for kick in 0..MAX_KICKS as u64 {
+            let victim = (u64::from(fingerprint) ^ kick).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 62;
+            let slot = index * BUCKET_SIZE + victim as usize;
+            std::mem::swap(&mut fingerprint, &mut self.slots[slot]);
+            swaps.push(slot);
+            index = self.alt_index(index, fingerprint);
+            if self.try_put(index, fingerprint) {
+                self.len += 1;
+                return Ok(());
+            }
+        }
+        for slot in swaps.into_iter().rev() {
+            std::mem::swap(&mut fingerprint, &mut self.slots[slot]);
+        }
+        Err(SketchError::Full)
+    }
+
+    /// Adds an item to the filter.
+    ///
+    /// Inserting the same item twice stores it twice, so that it has to be removed twice.
+    /// Returns `SketchError::Full` if no slot can be found, in which case the filter is left
+    /// unchanged.
+    pub fn insert<T: AsRef<[u8]> + ?Sized>(&mut self, item: &T) -> Result<(), SketchError> {
+        let (fingerprint, index) = self.fingerprint_and_index(item.as_ref());
+        self.put(index, fingerprint)
+    }
+
+    /// Returns false if the item is not in the filter, and true if it probably is.
+    pub fn contains<T: AsRef<[u8]> + ?Sized>(&self, item: &T) -> bool {
+        let (fingerprint, index) = self.fingerprint_and_index(item.as_ref());
+        self.bucket(index).contains(&fingerprint)
+            || self.bucket(self.alt_index(index, fingerprint)).contains(&fingerprint)
+    }
+
+    /// Removes one copy of an item from the filter. Returns false if it was not found.
+    pub fn remove<T: AsRef<[u8]> + ?Sized>(&mut self, item: &T) -> bool {
+        let (fingerprint, index) = self.fingerprint_and_index(item.as_ref());
+        let alt = self.alt_index(index, fingerprint);
+        for bucket in [index, alt].iter() {
+            if let Some(slot) = self.bucket_mut(*bucket).iter_mut().find(|slot| **slot == fingerprint) {
+                *slot = 0;
+                self.len -= 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Removes all the items.
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = 0);
+        self.len = 0;
+    }
+
+    /// Adds all the items of the other filter to this one.
+    ///
+    /// Both filters must have the same capacity and seed. Returns `SketchError::Full` if the
+    /// items do not all fit, in which case some of them may have been added.
+    pub fn merge(&mut self, other: &CuckooFilter) -> Result<(), SketchError> {
+        check_compatible("capacities", self.slots.len(), other.slots.len())?;
+        check_compatible("seeds", self.seed, other.seed)?;
+        for (position, &fingerprint) in other.slots.iter().enumerate() {
+            if fingerprint != 0 {
+                self.put(position / BUCKET_SIZE, fingerprint)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the filter: the header, the seed, the number of items and of slots, then the
+    /// slots as 16 bit fingerprints.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, 24 + 2 * self.slots.len());
+        encoder.u64(self.seed);
+        encoder.u64(self.len);
+        encoder.u64(self.slots.len() as u64);
+        for &slot in self.slots.iter() {
+            encoder.u16(slot);
+        }
+        encoder.finish()
+    }
+
+    /// Decodes a filter encoded with `to_bytes`.
+    pub fn from_bytes(input: &[u8]) -> Result<CuckooFilter, SketchError> {
+        let mut decoder = Decoder::new(MAGIC, input)?;
+        let seed = decoder.u64()?;
+        let len = decoder.u64()?;
+        let num_slots = decoder.len(2)?;
+        if num_slots < BUCKET_SIZE || !num_slots.is_power_of_two() {
+            return Err(SketchError::InvalidFormat(format!("invalid number of slots {}", num_slots)));
+        }
+        let slots = (0..num_slots).map(|_| decoder.u16()).collect::<Result<Vec<u16>, _>>()?;
+        decoder.finish()?;
+        if slots.iter().filter(|&&slot| slot != 0).count() as u64 != len {
+            return Err(SketchError::InvalidFormat("inconsistent number of items".to_string()));
+        }
+        Ok(CuckooFilter { slots, len, seed })
+    }
+}
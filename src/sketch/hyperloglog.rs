@@ -0,0 +1,136 @@
+use super::{check_compatible, item_hash, Decoder, Encoder, SketchError};
+
+const MAGIC: &[u8; 4] = b"EHLL";
+
+/// A HyperLogLog (Flajolet et al., 2007), for the approximate number of distinct items.
+///
+/// With a precision of `p` the sketch has `m = 2^p` one byte registers, and the relative
+/// standard error of the estimate is about `1.04 / sqrt(m)`: 1.6% with the default precision
+/// of 12 (4 KiB). Small cardinalities are counted with linear counting, which is close to
+/// exact.
+///
+/// ```
+/// use estahr::sketch::HyperLogLog;
+///
+/// let mut hll = HyperLogLog::new(12).unwrap();
+/// for i in 0..10_000 {
+///     hll.insert(&format!("user-{}", i));
+/// }
+/// let estimate = hll.estimate();
+/// assert!(estimate > 9_500.0 && estimate < 10_500.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+    precision: u8,
+    seed: u64,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog { registers: vec![0; 1 << 12], precision: 12, seed: 0 }
+    }
+}
+
+impl HyperLogLog {
+    /// Creates a sketch with `2^precision` registers. The precision must be from 4 to 18.
+    pub fn new(precision: u8) -> Result<HyperLogLog, SketchError> {
+        HyperLogLog::with_seed(precision, 0)
+    }
+
+    /// Creates a sketch with `2^precision` registers, hashing the items with the given seed.
+    /// Sketches with different seeds cannot be merged.
+    pub fn with_seed(precision: u8, seed: u64) -> Result<HyperLogLog, SketchError> {
+        if !(4..=18).contains(&precision) {
+            return Err(SketchError::InvalidParameter(
+                format!("precision must be from 4 to 18, got {}", precision)));
+        }
+        Ok(HyperLogLog { registers: vec![0; 1 << precision], precision, seed })
+    }
+
+    /// The precision, the number of bits of the hash that select a register.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// The relative standard error of the estimate, `1.04 / sqrt(2^precision)`.
+    pub fn standard_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+
+    /// Adds an item to the sketch.
+    pub fn insert<T: AsRef<[u8]> + ?Sized>(&mut self, item: &T) {
+        let (hash, _) = item_hash(item.as_ref(), self.seed);
+        let index = (hash >> (64 - self.precision)) as usize;
+        // The rank is the position of the first 1 bit in the rest of the hash, which is at
+        // most 65 - precision.
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() as u8 + 1;
+        let register = &mut self.registers[index];
+        *register = (*register).max(rank);
+    }
+
+    /// Estimates the number of distinct items inserted.
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self.registers.iter().map(|&register| 2f64.powi(-i32::from(register))).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|&&register| register == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities.
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+
+    /// Removes all the items.
+    pub fn clear(&mut self) {
+        self.registers.iter_mut().for_each(|register| *register = 0);
+    }
+
+    /// Adds all the items of the other sketch to this one, so that the estimate counts the
+    /// distinct items of the union.
+    ///
+    /// Both sketches must have the same precision and seed.
+    pub fn merge(&mut self, other: &HyperLogLog) -> Result<(), SketchError> {
+        check_compatible("precisions", self.precision, other.precision)?;
+        check_compatible("seeds", self.seed, other.seed)?;
+        for (register, &other_register) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = (*register).max(other_register);
+        }
+        Ok(())
+    }
+
+    /// Encodes the sketch: the header, the precision and seed, then one byte per register.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, 9 + self.registers.len());
+        encoder.u8(self.precision);
+        encoder.u64(self.seed);
+        for &register in self.registers.iter() {
+            encoder.u8(register);
+        }
+        encoder.finish()
+    }
+
+    /// Decodes a sketch encoded with `to_bytes`.
+    pub fn from_bytes(input: &[u8]) -> Result<HyperLogLog, SketchError> {
+        let mut decoder = Decoder::new(MAGIC, input)?;
+        let precision = decoder.u8()?;
+        let seed = decoder.u64()?;
+        let mut hll = HyperLogLog::with_seed(precision, seed)
+            .map_err(|e| SketchError::InvalidFormat(e.to_string()))?;
+        let max_rank = 65 - precision;
+        hll.registers.copy_from_slice(decoder.take(1 << precision)?);
+        decoder.finish()?;
+        if hll.registers.iter().any(|&register| register > max_rank) {
+            return Err(SketchError::InvalidFormat("register out of range".to_string()));
+        }
+        Ok(hll)
+    }
+}
@@ -0,0 +1,161 @@
+//! This module provides probabilistic data structures for very large sets of strings.
+//!
+//! The sketches included are:
+//! 1. Bloom filter, for set membership with false positives but no false negatives.
+//! 2. Cuckoo filter, like a Bloom filter but supporting removal, and smaller for low false
+//!    positive rates.
+//! 3. Count-Min sketch, for the approximate frequency of items in a stream.
+//! 4. HyperLogLog, for the approximate number of distinct items.
+//!
+//! The items are hashed with XXH3 (see `ChecksumAlgorithm::XXH3_128`), seeded so that
+//! independent sketches can be built over the same items. Every sketch documents its error
+//! bounds, can be merged with another sketch of the same parameters (to combine shards), and
+//! can be written to and read back from a compact binary format with `to_bytes` and
+//! `from_bytes`. The format is little endian, and starts with a four byte magic and a
+//! version byte.
+
+mod bloom;
+mod countmin;
+mod cuckoo;
+mod hyperloglog;
+
+use std::{error, fmt};
+
+pub use self::bloom::BloomFilter;
+pub use self::countmin::CountMinSketch;
+pub use self::cuckoo::CuckooFilter;
+pub use self::hyperloglog::HyperLogLog;
+
+const FORMAT_VERSION: u8 = 1;
+
+/// The error returned when a sketch cannot be built, merged or decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SketchError {
+    /// A parameter is out of its valid range.
+    InvalidParameter(String),
+    /// The two sketches cannot be merged, because they were built with different parameters
+    /// or seeds.
+    Incompatible(String),
+    /// The bytes are not a valid encoding of the sketch.
+    InvalidFormat(String),
+    /// The cuckoo filter is too full to take more items.
+    Full,
+}
+
+impl fmt::Display for SketchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SketchError::InvalidParameter(reason) => write!(f, "invalid sketch parameter: {}", reason),
+            SketchError::Incompatible(reason) => write!(f, "incompatible sketches: {}", reason),
+            SketchError::InvalidFormat(reason) => write!(f, "invalid sketch encoding: {}", reason),
+            SketchError::Full => write!(f, "the filter is full"),
+        }
+    }
+}
+
+impl error::Error for SketchError {}
+
+/// Hashes an item into two independent 64 bit values.
+fn item_hash(item: &[u8], seed: u64) -> (u64, u64) {
+    let hash = xxhash_rust::xxh3::xxh3_128_with_seed(item, seed);
+    (hash as u64, (hash >> 64) as u64)
+}
+
+fn check_compatible<T: PartialEq + fmt::Debug>(what: &str, ours: T, theirs: T) -> Result<(), SketchError> {
+    if ours != theirs {
+        return Err(SketchError::Incompatible(format!("{} differ: {:?} and {:?}", what, ours, theirs)));
+    }
+    Ok(())
+}
+
+/// Writes the header and fields of a sketch encoding.
+struct Encoder {
+    output: Vec<u8>,
+}
+
+impl Encoder {
+    fn new(magic: &[u8; 4], capacity: usize) -> Encoder {
+        let mut output = Vec::with_capacity(capacity + 5);
+        output.extend_from_slice(magic);
+        output.push(FORMAT_VERSION);
+        Encoder { output }
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.output.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.output.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.output.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.output
+    }
+}
+
+/// Reads the header and fields of a sketch encoding.
+struct Decoder<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn new(magic: &[u8; 4], input: &'a [u8]) -> Result<Decoder<'a>, SketchError> {
+        let mut decoder = Decoder { input };
+        if decoder.take(4)? != magic {
+            return Err(SketchError::InvalidFormat("wrong magic".to_string()));
+        }
+        let version = decoder.u8()?;
+        if version != FORMAT_VERSION {
+            return Err(SketchError::InvalidFormat(format!("unsupported version {}", version)));
+        }
+        Ok(decoder)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SketchError> {
+        if self.input.len() < len {
+            return Err(SketchError::InvalidFormat("truncated input".to_string()));
+        }
+        let (taken, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, SketchError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SketchError> {
+        let mut bytes = [0; 2];
+        bytes.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, SketchError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Reads a length field, checking that the rest of the input can hold that many
+    /// elements of `element_len` bytes, so that corrupt input cannot trigger a huge
+    /// allocation.
+    fn len(&mut self, element_len: usize) -> Result<usize, SketchError> {
+        let len = self.u64()?;
+        if len.saturating_mul(element_len as u64) > self.input.len() as u64 {
+            return Err(SketchError::InvalidFormat("truncated input".to_string()));
+        }
+        Ok(len as usize)
+    }
+
+    fn finish(self) -> Result<(), SketchError> {
+        if !self.input.is_empty() {
+            return Err(SketchError::InvalidFormat(format!("{} trailing bytes", self.input.len())));
+        }
+        Ok(())
+    }
+}
@@ -158,7 +158,7 @@ fn jaro_winkler_distance<A: PartialEq>(it_a: &[A], it_b: &[A]) -> f64 {
     if matches == 0 { return 1.0; }
 
     // Transpositions calculation
-    let mut k = 0 as usize;
+    let mut k = 0_usize;
     for i in 0..len_a {
         if !vec_a[i] {
             continue;
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use xxhash_rust::xxh3::{xxh3_128, xxh3_64};
+
+/// How a string is split into the overlapping pieces (shingles) that fingerprints are built
+/// from.
+///
+/// The text is used as is: lowercase it, strip punctuation or collapse whitespace first if
+/// those differences should not count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shingling {
+    /// Every run of this many consecutive characters.
+    Chars(usize),
+    /// Every run of this many consecutive words, split on whitespace.
+    Words(usize),
+}
+
+/// Hashes every shingle of the text. A text shorter than one shingle is a single shingle,
+/// and an empty text has none.
+fn shingle_hashes(text: &str, shingling: Shingling) -> Vec<u128> {
+    match shingling {
+        Shingling::Chars(size) => {
+            let size = size.max(1);
+            let mut bounds: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+            bounds.push(text.len());
+            let count = bounds.len() - 1;
+            if count == 0 {
+                return Vec::new();
+            }
+            if count <= size {
+                return vec![xxh3_128(text.as_bytes())];
+            }
+            (0..=count - size).map(|i| xxh3_128(&text.as_bytes()[bounds[i]..bounds[i + size]])).collect()
+        }
+        Shingling::Words(size) => {
+            let size = size.max(1);
+            let words: Vec<&str> = text.split_whitespace().collect();
+            if words.is_empty() {
+                return Vec::new();
+            }
+            words.windows(size.min(words.len()))
+                .map(|window| xxh3_128(window.join(" ").as_bytes()))
+                .collect()
+        }
+    }
+}
+
+/// Computes the 64 bit SimHash (Charikar) of the string.
+///
+/// Similar strings get fingerprints that differ in few bits, so the `hamming` distance
+/// between two fingerprints estimates how different the strings are. Fingerprints differing
+/// in 3 bits or fewer are the usual threshold for near-duplicates.
+pub fn simhash64(text: &str, shingling: Shingling) -> u64 {
+    let mut weights = [0i64; 64];
+    for hash in shingle_hashes(text, shingling) {
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if (hash >> bit) & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    weights.iter().enumerate().fold(0, |fingerprint, (bit, &weight)| {
+        if weight > 0 { fingerprint | (1 << bit) } else { fingerprint }
+    })
+}
+
+/// Computes the 128 bit SimHash (Charikar) of the string.
+///
+/// See `simhash64`. The longer fingerprint gives a finer estimate of the difference.
+pub fn simhash128(text: &str, shingling: Shingling) -> u128 {
+    let mut weights = [0i64; 128];
+    for hash in shingle_hashes(text, shingling) {
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if (hash >> bit) & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    weights.iter().enumerate().fold(0, |fingerprint, (bit, &weight)| {
+        if weight > 0 { fingerprint | (1 << bit) } else { fingerprint }
+    })
+}
+
+/// Calculates the bitwise Hamming distance between two fingerprints, the number of bits
+/// that differ.
+///
+/// Works with both `simhash64` and `simhash128` fingerprints.
+pub fn hamming<T: Into<u128>>(fingerprint_a: T, fingerprint_b: T) -> usize {
+    (fingerprint_a.into() ^ fingerprint_b.into()).count_ones() as usize
+}
+
+/// The finalizer of MurmurHash3, which turns the shingle hash into independent hashes for
+/// each MinHash slot.
+fn mix(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+/// Computes MinHash signatures of strings.
+///
+/// The fraction of slots two signatures have in common estimates the Jaccard similarity of
+/// the shingle sets of the strings. More hashes give a better estimate: the standard error
+/// is about `1 / sqrt(num_hashes)`.
+///
+/// ```
+/// use estahr::strings::{MinHasher, Shingling};
+///
+/// let minhasher = MinHasher::new(128, Shingling::Words(2));
+/// let a = minhasher.signature("the quick brown fox jumps over the lazy dog");
+/// let b = minhasher.signature("the quick brown fox jumped over the lazy dog");
+/// assert!(a.jaccard(&b) > 0.3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MinHasher {
+    seeds: Vec<u64>,
+    shingling: Shingling,
+}
+
+impl MinHasher {
+    /// Creates a MinHasher with `num_hashes` slots per signature.
+    ///
+    /// The seeds are fixed, so signatures from different `MinHasher`s with the same number
+    /// of hashes and shingling can be compared.
+    pub fn new(num_hashes: usize, shingling: Shingling) -> MinHasher {
+        let seeds = (0..num_hashes as u64).map(|i| mix(i.wrapping_add(0x9e37_79b9_7f4a_7c15))).collect();
+        MinHasher { seeds, shingling }
+    }
+
+    /// The number of slots in each signature.
+    pub fn num_hashes(&self) -> usize {
+        self.seeds.len()
+    }
+
+    /// Computes the MinHash signature of the string.
+    ///
+    /// An empty string has no shingles, and its signature only matches other empty strings.
+    pub fn signature(&self, text: &str) -> MinHashSignature {
+        let mut slots = vec![u64::MAX; self.seeds.len()];
+        for hash in shingle_hashes(text, self.shingling) {
+            let hash = hash as u64;
+            for (slot, &seed) in slots.iter_mut().zip(self.seeds.iter()) {
+                *slot = (*slot).min(mix(hash ^ seed));
+            }
+        }
+        MinHashSignature(slots)
+    }
+}
+
+/// The MinHash signature of a string, returned by `MinHasher::signature`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MinHashSignature(Vec<u64>);
+
+impl MinHashSignature {
+    /// The slots of the signature.
+    pub fn as_slice(&self) -> &[u64] {
+        &self.0
+    }
+
+    /// Estimates the Jaccard similarity of the two strings, between 0 and 1 (identical).
+    ///
+    /// Signatures of different lengths are compared over their common slots.
+    pub fn jaccard(&self, other: &MinHashSignature) -> f64 {
+        let slots = self.0.len().min(other.0.len());
+        if slots == 0 {
+            return 0.0;
+        }
+        let equal = self.0.iter().zip(other.0.iter()).filter(|(a, b)| a == b).count();
+        equal as f64 / slots as f64
+    }
+}
+
+/// A locality-sensitive hashing index over MinHash signatures, to find near-duplicates
+/// without comparing every pair.
+///
+/// Each signature is split into `bands` bands of `rows` slots. Two signatures become
+/// candidates when all the slots of at least one band are equal, which happens with high
+/// probability above a Jaccard similarity of about `(1 / bands) ^ (1 / rows)` and with low
+/// probability below it.
+///
+/// ```
+/// use estahr::strings::{LshIndex, MinHasher, Shingling};
+///
+/// let minhasher = MinHasher::new(128, Shingling::Chars(5));
+/// let mut index = LshIndex::for_threshold(128, 0.5);
+/// index.insert(minhasher.signature("Stainless steel water bottle, 750 ml, keeps drinks cold"));
+/// index.insert(minhasher.signature("Wireless noise cancelling headphones with 30 hour battery"));
+/// index.insert(minhasher.signature("Stainless steel water bottle, 750ml, keeps drinks cold!"));
+/// let pairs: Vec<_> = index.near_duplicates(0.5).into_iter().map(|(a, b, _)| (a, b)).collect();
+/// assert_eq!(pairs, vec![(0, 2)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LshIndex {
+    bands: usize,
+    rows: usize,
+    buckets: Vec<HashMap<u64, Vec<usize>>>,
+    signatures: Vec<MinHashSignature>,
+}
+
+impl LshIndex {
+    /// Creates an empty index with the given number of bands and rows per band.
+    ///
+    /// The signatures inserted must have at least `bands * rows` slots.
+    pub fn new(bands: usize, rows: usize) -> LshIndex {
+        let bands = bands.max(1);
+        LshIndex { bands, rows: rows.max(1), buckets: vec![HashMap::new(); bands], signatures: Vec::new() }
+    }
+
+    /// Creates an empty index for signatures of `num_hashes` slots, choosing the bands and
+    /// rows whose threshold is closest to the given Jaccard similarity.
+    pub fn for_threshold(num_hashes: usize, threshold: f64) -> LshIndex {
+        let num_hashes = num_hashes.max(1);
+        let (bands, rows) = (1..=num_hashes)
+            .map(|bands| (bands, num_hashes / bands))
+            .min_by(|&(b1, r1), &(b2, r2)| {
+                let error = |bands: usize, rows: usize| (lsh_threshold(bands, rows) - threshold).abs();
+                error(b1, r1).total_cmp(&error(b2, r2))
+            })
+            .unwrap_or((1, num_hashes));
+        LshIndex::new(bands, rows)
+    }
+
+    /// The number of bands.
+    pub fn bands(&self) -> usize {
+        self.bands
+    }
+
+    /// The number of slots per band.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The Jaccard similarity around which signatures start becoming candidates.
+    pub fn threshold(&self) -> f64 {
+        lsh_threshold(self.bands, self.rows)
+    }
+
+    /// The number of signatures in the index.
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Returns true if the index has no signatures.
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    fn band_keys<'a>(&'a self, signature: &'a MinHashSignature) -> impl Iterator<Item=u64> + 'a {
+        assert!(signature.0.len() >= self.bands * self.rows,
+                "the signature has {} slots, the index needs {}", signature.0.len(), self.bands * self.rows);
+        signature.0.chunks(self.rows).take(self.bands).map(|band| {
+            let bytes: Vec<u8> = band.iter().flat_map(|slot| slot.to_le_bytes()).collect();
+            xxh3_64(&bytes)
+        })
+    }
+
+    /// Adds a signature to the index and returns its id, which is the number of signatures
+    /// inserted before it.
+    ///
+    /// Panics if the signature has fewer than `bands * rows` slots.
+    pub fn insert(&mut self, signature: MinHashSignature) -> usize {
+        let id = self.signatures.len();
+        let keys: Vec<u64> = self.band_keys(&signature).collect();
+        for (bucket, key) in self.buckets.iter_mut().zip(keys) {
+            bucket.entry(key).or_default().push(id);
+        }
+        self.signatures.push(signature);
+        id
+    }
+
+    /// Returns the ids of the signatures that share at least one band with the given one,
+    /// sorted.
+    ///
+    /// Panics if the signature has fewer than `bands * rows` slots.
+    pub fn query(&self, signature: &MinHashSignature) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.band_keys(signature)
+            .zip(self.buckets.iter())
+            .filter_map(|(key, bucket)| bucket.get(&key))
+            .flatten()
+            .cloned()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Returns every pair of ids `(a, b)`, with `a < b`, that share at least one band,
+    /// sorted.
+    pub fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for bucket in self.buckets.iter() {
+            for ids in bucket.values() {
+                for (i, &a) in ids.iter().enumerate() {
+                    pairs.extend(ids[i + 1..].iter().map(|&b| (a, b)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+
+    /// Returns the candidate pairs whose estimated Jaccard similarity is at least
+    /// `threshold`, with that estimate, sorted by id.
+    pub fn near_duplicates(&self, threshold: f64) -> Vec<(usize, usize, f64)> {
+        self.candidate_pairs().into_iter()
+            .map(|(a, b)| (a, b, self.signatures[a].jaccard(&self.signatures[b])))
+            .filter(|&(_, _, similarity)| similarity >= threshold)
+            .collect()
+    }
+}
+
+fn lsh_threshold(bands: usize, rows: usize) -> f64 {
+    (1.0 / bands as f64).powf(1.0 / rows as f64)
+}
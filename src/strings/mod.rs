@@ -11,5 +11,7 @@
 //! distance that is normalized (between 0 and 1; 1 is identical)
 
 mod distance;
+mod fingerprint;
 
-pub use distance::{hamming_ascii, hamming_bytes, levenshtein_ascii, levenshtein_bytes, jaro_winkler_ascii, jaro_winkler_bytes};
\ No newline at end of file
+pub use distance::{hamming_ascii, hamming_bytes, levenshtein_ascii, levenshtein_bytes, jaro_winkler_ascii, jaro_winkler_bytes};
+pub use fingerprint::{hamming, simhash128, simhash64, LshIndex, MinHashSignature, MinHasher, Shingling};
\ No newline at end of file
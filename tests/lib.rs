@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod ext_tests {
     use estahr::hash::*;
-    use estahr::strings::*;
+    use estahr::chunking::{self, ChunkerAlgorithm, ChunkerOptions};
+    use estahr::manifest::{self, ManifestFormat, VerifyStatus};
+    use estahr::merkle::MerkleTree;
 
     const FILENAME_SMALL: &str = "small_600.txt";
     const FILENAME_LARGE: &str = "large_1800.txt";
@@ -11,73 +13,734 @@ mod ext_tests {
         path.push("tests");
         path.push(file_name);
         if path.exists() {
-            return path.into_os_string().to_str().unwrap().to_string();
+            path.into_os_string().to_str().unwrap().to_string()
         } else {
-            panic!(format!("Cannot find file : <{}> in tests folder", file_name));
+            panic!("Cannot find file : <{}> in tests folder", file_name);
         }
     }
 
     #[test]
     fn test_md5_hash_file() {
-        assert_eq!(hash_file(HashAlgorithm::MD5, get_tests_file_path_string(FILENAME_SMALL).as_ref(),
-                             true).unwrap(), "bc3865fa3378733b0a0a665a2d943595");
-        assert_eq!(hash_file(HashAlgorithm::MD5, get_tests_file_path_string(FILENAME_LARGE).as_ref(),
-                             true).unwrap(), "658528b6652f8eca8b1321af88ec2bcf");
+        assert_eq!(hash_file(HashAlgorithm::MD5, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "bc3865fa3378733b0a0a665a2d943595");
+        assert_eq!(hash_file(HashAlgorithm::MD5, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "658528b6652f8eca8b1321af88ec2bcf");
     }
 
     #[test]
     fn test_sha256_hash_file() {
-        assert_eq!(hash_file(HashAlgorithm::SHA2_256, get_tests_file_path_string(FILENAME_SMALL).as_ref(),
-                             true).unwrap(), "c675343eb3f9331772d98f01e78e0f7e12e977f4c12200df599906400d18e5b7");
-        assert_eq!(hash_file(HashAlgorithm::SHA2_256, get_tests_file_path_string(FILENAME_LARGE).as_ref(),
-                             true).unwrap(), "d210e97734ae6338f7c7a43eface2a7444a258ba398280d82ac27959690d9ca6");
+        assert_eq!(hash_file(HashAlgorithm::SHA2_256, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "c675343eb3f9331772d98f01e78e0f7e12e977f4c12200df599906400d18e5b7");
+        assert_eq!(hash_file(HashAlgorithm::SHA2_256, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "d210e97734ae6338f7c7a43eface2a7444a258ba398280d82ac27959690d9ca6");
     }
 
     #[test]
     fn test_sha512_hash_file() {
-        assert_eq!(hash_file(HashAlgorithm::SHA2_512, get_tests_file_path_string(FILENAME_SMALL).as_ref(),
-                             true).unwrap(), "28dd3fab391cfae8461d7942d9ab642e00a3b164518180e71f032765f717384027146f58b566d06db63c6b19a05e9ae417ba0bd8c9ab4007f2cd692b4f5e5d35");
-        assert_eq!(hash_file(HashAlgorithm::SHA2_512, get_tests_file_path_string(FILENAME_LARGE).as_ref(),
-                             true).unwrap(), "ca09aa89ab8eea844a1bcc0d337a8af3b8b8351f0836b3d7dd2ea09f5ce22cbec299c0ab4636a306625236bd45a9d9a49d0c50f10ba6f3299bc781f09c816720");
+        assert_eq!(hash_file(HashAlgorithm::SHA2_512, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "28dd3fab391cfae8461d7942d9ab642e00a3b164518180e71f032765f717384027146f58b566d06db63c6b19a05e9ae417ba0bd8c9ab4007f2cd692b4f5e5d35");
+        assert_eq!(hash_file(HashAlgorithm::SHA2_512, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "ca09aa89ab8eea844a1bcc0d337a8af3b8b8351f0836b3d7dd2ea09f5ce22cbec299c0ab4636a306625236bd45a9d9a49d0c50f10ba6f3299bc781f09c816720");
     }
 
     #[test]
     fn test_sha3_256_hash_file() {
-        assert_eq!(hash_file(HashAlgorithm::SHA3_256, get_tests_file_path_string(FILENAME_SMALL).as_ref(),
-                             true).unwrap(), "87632a6f642e8440fb849e217e41553e2e719fb2701559462ae95a1449ccf83e");
-        assert_eq!(hash_file(HashAlgorithm::SHA3_256, get_tests_file_path_string(FILENAME_LARGE).as_ref(),
-                             true).unwrap(), "142ae8a76c47552ac10d8c92c7e65a34efc27bde975a8bf050cd1a28846b1508");
+        assert_eq!(hash_file(HashAlgorithm::SHA3_256, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "87632a6f642e8440fb849e217e41553e2e719fb2701559462ae95a1449ccf83e");
+        assert_eq!(hash_file(HashAlgorithm::SHA3_256, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "142ae8a76c47552ac10d8c92c7e65a34efc27bde975a8bf050cd1a28846b1508");
     }
 
     #[test]
     fn test_sha3_512_hash_file() {
-        assert_eq!(hash_file(HashAlgorithm::SHA3_512, get_tests_file_path_string(FILENAME_SMALL).as_ref(),
-                             true).unwrap(), "a00039bd24ade99e8a67748bb9828c4a123ca4dc98490e408623d710c0b83cfe1bfc5646d2e29fd79509bac897df506d910b6175c5d2f8b469a1c21cb57c48be");
-        assert_eq!(hash_file(HashAlgorithm::SHA3_512, get_tests_file_path_string(FILENAME_LARGE).as_ref(),
-                             true).unwrap(), "08cb259551963e3ff945b1053e3de3415177b341adb853cfad12d1aa22b30473d1c988c2f37cc113f261b283e023b588b3a39eedf930d790b0339830b1253c33");
+        assert_eq!(hash_file(HashAlgorithm::SHA3_512, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "a00039bd24ade99e8a67748bb9828c4a123ca4dc98490e408623d710c0b83cfe1bfc5646d2e29fd79509bac897df506d910b6175c5d2f8b469a1c21cb57c48be");
+        assert_eq!(hash_file(HashAlgorithm::SHA3_512, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "08cb259551963e3ff945b1053e3de3415177b341adb853cfad12d1aa22b30473d1c988c2f37cc113f261b283e023b588b3a39eedf930d790b0339830b1253c33");
     }
 
     #[test]
     fn test_blake2s_hash_file() {
-        assert_eq!(hash_file(HashAlgorithm::BLAKE2S, get_tests_file_path_string(FILENAME_SMALL).as_ref(),
-                             true).unwrap(), "c4bdf43b4a6d9d1bd626b11560ca79dc986feb033cc36b4c9c0daf0fe7b93c61");
-        assert_eq!(hash_file(HashAlgorithm::BLAKE2S, get_tests_file_path_string(FILENAME_LARGE).as_ref(),
-                             true).unwrap(), "149e2d479705adab174fc6bd177543e42a3e8ace79d4cd1592e9cd1bcbc3a8d0");
+        assert_eq!(hash_file(HashAlgorithm::BLAKE2S, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "c4bdf43b4a6d9d1bd626b11560ca79dc986feb033cc36b4c9c0daf0fe7b93c61");
+        assert_eq!(hash_file(HashAlgorithm::BLAKE2S, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "149e2d479705adab174fc6bd177543e42a3e8ace79d4cd1592e9cd1bcbc3a8d0");
     }
 
     #[test]
     fn test_blake2b_hash_file() {
-        assert_eq!(hash_file(HashAlgorithm::BLAKE2B, get_tests_file_path_string(FILENAME_SMALL).as_ref(),
-                             true).unwrap(), "2392fdbff0ceb25c10673c7eaf1a907be0517e939aec9943cdf85b9b11004beaea1f7da1dac8752554a76cb35e4a836b34cca0cd08ebc48374043dbcabcbd2b8");
-        assert_eq!(hash_file(HashAlgorithm::BLAKE2B, get_tests_file_path_string(FILENAME_LARGE).as_ref(),
-                             true).unwrap(), "8f5a4fd4a7009432d43a59b7cf0896691aa611a4d683b3e8f9e0186c96a7a9c2156bd3b7e0767218ee238e58abd0b6870da84470909f729b9d62b95fb35946f1");
+        assert_eq!(hash_file(HashAlgorithm::BLAKE2B, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "2392fdbff0ceb25c10673c7eaf1a907be0517e939aec9943cdf85b9b11004beaea1f7da1dac8752554a76cb35e4a836b34cca0cd08ebc48374043dbcabcbd2b8");
+        assert_eq!(hash_file(HashAlgorithm::BLAKE2B, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "8f5a4fd4a7009432d43a59b7cf0896691aa611a4d683b3e8f9e0186c96a7a9c2156bd3b7e0767218ee238e58abd0b6870da84470909f729b9d62b95fb35946f1");
     }
 
     #[test]
     fn test_blake3_hash_file() {
-        assert_eq!(hash_file(HashAlgorithm::BLAKE3, get_tests_file_path_string(FILENAME_SMALL).as_ref(),
-                             true).unwrap(), "f27c992490b785efd646370d102f86cce36448531e2885017b5ab8269d826d92");
-        assert_eq!(hash_file(HashAlgorithm::BLAKE3, get_tests_file_path_string(FILENAME_LARGE).as_ref(),
-                             true).unwrap(), "b4b805595bac0db59dcb72664148b72d8e5f9fed362cccfc913cc11a8dbdc0b5");
+        assert_eq!(hash_file(HashAlgorithm::BLAKE3, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "f27c992490b785efd646370d102f86cce36448531e2885017b5ab8269d826d92");
+        assert_eq!(hash_file(HashAlgorithm::BLAKE3, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "b4b805595bac0db59dcb72664148b72d8e5f9fed362cccfc913cc11a8dbdc0b5");
+    }
+
+    #[test]
+    fn test_sha1_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::SHA1, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "0ff88ef690cdd5cbe37e4bfb845e7835bf3934cf");
+        assert_eq!(hash_file(HashAlgorithm::SHA1, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "3549bc4dffdb2b7aa2721faa77ce771232b7a523");
+    }
+
+    #[test]
+    fn test_sha224_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::SHA2_224, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "9e84f4081b2b79d511d8f400b50508b935e7de68bdd91c20daa35f02");
+        assert_eq!(hash_file(HashAlgorithm::SHA2_224, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "3bb270c5fb1b8bcd2ad08e60474622f8d83807ecf0585dfb61ad03ff");
+    }
+
+    #[test]
+    fn test_sha384_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::SHA2_384, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "91e0a7f760e2f75a2b7ebad23705ee1ad52d74d9ea574b11f32de7cb6c2540b26a9fecc26b3a7ab55ffb62800328f929");
+        assert_eq!(hash_file(HashAlgorithm::SHA2_384, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "edb556f1d3f1e8f297a781cafbc484525349a7c0ca95f27ef57db7a5874e89c8a8ea947cc986d2cab39cb937e199e6de");
+    }
+
+    #[test]
+    fn test_sha512_256_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::SHA2_512_256, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "abcb83f9b14c3957814922aeb6dfb8e0434ef9c73edf4c61ed68a1e875e8beeb");
+        assert_eq!(hash_file(HashAlgorithm::SHA2_512_256, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "57554271d42ff3e04666cccf8a637829d4a990525e0d97992193ffab0c22c02d");
+    }
+
+    #[test]
+    fn test_sha3_224_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::SHA3_224, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "e3f49af4544bd9cea726b52533ee3147d2402d79e01c0d7f6e26efc6");
+        assert_eq!(hash_file(HashAlgorithm::SHA3_224, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "d4c17de51f422ac5366d0712dc35b9d118e101f58bc3d8a24f676be3");
+    }
+
+    #[test]
+    fn test_sha3_384_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::SHA3_384, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "2531188819f12388d12dc7190b2e8f4b26186db669116a0d2e983440a0e0be009be13b45b272bcd91bb4a3dde3a27a6e");
+        assert_eq!(hash_file(HashAlgorithm::SHA3_384, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "99e2a1dd8d8b6d65568c734c5adf6c1675afd8c04d9b8ee4bc18b766dd6a54429efe0f18e1157079a820b8bbe56a8ae0");
+    }
+
+    #[test]
+    fn test_keccak256_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::KECCAK256, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "a01cb428b7739a224377bdf92421022670730a0d5555737bca68a0fcbdd6350c");
+        assert_eq!(hash_file(HashAlgorithm::KECCAK256, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "732b07e1514ff5cf61be96ba94ba61b1ac318656d4214764e538fe5420ee0a05");
+    }
+
+    #[test]
+    fn test_ripemd160_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::RIPEMD160, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "d087778d6c6de7823dc6028447505391dda6d9b7");
+        assert_eq!(hash_file(HashAlgorithm::RIPEMD160, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "8b38877ccd6b67e3edd952b970f4d1ef63862761");
+    }
+
+    #[test]
+    fn test_whirlpool_hash_file() {
+        assert_eq!(hash_file(HashAlgorithm::WHIRLPOOL, get_tests_file_path_string(FILENAME_SMALL))
+                             .unwrap().to_hex(), "0912b573af4d1ad17078d69d4a822cbed6397f3847712ae9e11498da9060084eb3d59d6540ab49fce48d1f3cf61043d376f89cd7467d0c01939ab290f63a0cbf");
+        assert_eq!(hash_file(HashAlgorithm::WHIRLPOOL, get_tests_file_path_string(FILENAME_LARGE))
+                             .unwrap().to_hex(), "054bbb4f38d7a3e42afe28c227cb59932bd270e6ee705482cbba4d2d4c8383f55c2d77461350c740784671d0bc0188b2f368f004bafb9e0a0cb029383de91c0d");
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        let path = get_tests_file_path_string(FILENAME_SMALL);
+        let file = std::fs::File::open(&path).unwrap();
+        assert_eq!(hash_reader(HashAlgorithm::SHA2_256, file).unwrap().to_hex(),
+                   "c675343eb3f9331772d98f01e78e0f7e12e977f4c12200df599906400d18e5b7");
+        assert_eq!(hash_reader(HashAlgorithm::SHA2_256, &b"a"[..]).unwrap(),
+                   hash_string(HashAlgorithm::SHA2_256, "a"));
+        assert_eq!(hash_file(HashAlgorithm::SHA2_256, std::path::Path::new(&path)).unwrap(),
+                   hash_file(HashAlgorithm::SHA2_256, &path).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hash_file_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut path = std::env::temp_dir();
+        path.push(std::ffi::OsStr::from_bytes(b"estahr_non_utf8_\xff.txt"));
+        std::fs::copy(get_tests_file_path_string(FILENAME_SMALL), &path).unwrap();
+        let digest = hash_file(HashAlgorithm::MD5, &path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(digest.unwrap().to_hex(), "bc3865fa3378733b0a0a665a2d943595");
+    }
+
+    #[test]
+    fn test_hash_file_multi() {
+        let algorithms = [HashAlgorithm::MD5, HashAlgorithm::SHA2_256, HashAlgorithm::BLAKE3];
+        let path = get_tests_file_path_string(FILENAME_LARGE);
+        let expected: Vec<Digest> = algorithms.iter().map(|&a| hash_file(a, &path).unwrap()).collect();
+
+        assert_eq!(hash_file_multi(&algorithms, &path).unwrap(), expected);
+        assert_eq!(hash_file_multi_threaded(&algorithms, &path).unwrap(), expected);
+        assert_eq!(hash_reader_multi(&algorithms, std::fs::File::open(&path).unwrap()).unwrap(), expected);
+        assert_eq!(expected[0].to_hex(), "658528b6652f8eca8b1321af88ec2bcf");
+        assert!(hash_file_multi_threaded(&algorithms, "does_not_exist.txt").is_err());
+    }
+
+    #[test]
+    fn test_hash_file_with_options() {
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i: u32| (i % 251) as u8).collect();
+        let mut path = std::env::temp_dir();
+        path.push("estahr_hash_file_with_options.bin");
+        std::fs::write(&path, &data).unwrap();
+
+        let mapped = hash_file_with_options(HashAlgorithm::BLAKE3, &path, &HashOptions::new()).unwrap();
+        let streamed = hash_file_with_options(HashAlgorithm::BLAKE3, &path, &HashOptions::new().mmap(false)).unwrap();
+        let small_buffer = hash_file_with_options(HashAlgorithm::SHA2_256, &path, &HashOptions::new().buffer_size(7)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mapped, hash_bytes(HashAlgorithm::BLAKE3, &data));
+        assert_eq!(streamed, mapped);
+        assert_eq!(small_buffer, hash_bytes(HashAlgorithm::SHA2_256, &data));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hash_file_special_file() {
+        assert_eq!(hash_file(HashAlgorithm::BLAKE3, "/dev/null").unwrap(), hash_string(HashAlgorithm::BLAKE3, ""));
+    }
+
+    #[test]
+    fn test_manifest_generate_and_verify() {
+        let mut dir = std::env::temp_dir();
+        dir.push("estahr_manifest_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(get_tests_file_path_string(FILENAME_SMALL), dir.join(FILENAME_SMALL)).unwrap();
+        std::fs::copy(get_tests_file_path_string(FILENAME_LARGE), dir.join(FILENAME_LARGE)).unwrap();
+        std::fs::write(dir.join("changed.txt"), "before").unwrap();
+        std::fs::write(dir.join("removed.txt"), "gone").unwrap();
+
+        let names = [FILENAME_SMALL, FILENAME_LARGE, "changed.txt", "removed.txt"];
+        let entries: Vec<_> = manifest::generate(HashAlgorithm::SHA2_256, names.iter().map(|n| dir.join(n)))
+            .unwrap()
+            .into_iter()
+            .map(|mut entry| {
+                entry.path = entry.path.strip_prefix(&dir).unwrap().to_path_buf();
+                entry
+            })
+            .collect();
+
+        let gnu = manifest::render(&entries, ManifestFormat::Gnu);
+        assert!(gnu.starts_with("c675343eb3f9331772d98f01e78e0f7e12e977f4c12200df599906400d18e5b7  small_600.txt\n"));
+        let bsd = manifest::render(&entries, ManifestFormat::Bsd);
+        assert!(bsd.starts_with("SHA256 (small_600.txt) = c675343eb3f9331772d98f01e78e0f7e12e977f4c12200df599906400d18e5b7\n"));
+        assert_eq!(manifest::parse(&gnu, HashAlgorithm::SHA2_256).unwrap(), entries);
+        assert_eq!(manifest::parse(&bsd, HashAlgorithm::MD5).unwrap(), entries);
+
+        std::fs::write(dir.join("changed.txt"), "after").unwrap();
+        std::fs::remove_file(dir.join("removed.txt")).unwrap();
+        let results = manifest::verify(&entries, &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        assert!(matches!(results[2].status, VerifyStatus::Failed));
+        assert!(matches!(results[3].status, VerifyStatus::Missing));
+    }
+
+    fn make_tree(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_hash_tree() {
+        let files = [("a.txt", "alpha"), ("sub/b.txt", "beta"), ("sub/deeper/c.txt", "gamma")];
+        let reversed: Vec<_> = files.iter().rev().cloned().collect();
+        let tree_a = make_tree("estahr_tree_a", &files);
+        let tree_b = make_tree("estahr_tree_b", &reversed);
+        let tree_c = make_tree("estahr_tree_c", &[("a.txt", "alpha"), ("sub/b.txt", "beta"), ("sub/deeper/c.txt", "delta")]);
+        let tree_d = make_tree("estahr_tree_d", &[("a.txt", "alpha"), ("sub/b.txt", "beta"), ("sub/c.txt", "gamma")]);
+        let tree_e = make_tree("estahr_tree_e", &[("a.txt", "alpha"), ("sub/b.txt", "beta"), ("sub/deeper/c.txt", "gamma"),
+            ("sub/scratch.tmp", "noise"), ("build/out.o", "noise")]);
+
+        let digest_a = hash_tree(HashAlgorithm::SHA2_256, &tree_a).unwrap();
+        assert_eq!(hash_tree(HashAlgorithm::SHA2_256, &tree_b).unwrap(), digest_a);
+        assert_ne!(hash_tree(HashAlgorithm::SHA2_256, &tree_c).unwrap(), digest_a);
+        assert_ne!(hash_tree(HashAlgorithm::SHA2_256, &tree_d).unwrap(), digest_a);
+        assert_ne!(hash_tree(HashAlgorithm::SHA2_256, &tree_e).unwrap(), digest_a);
+        let options = TreeOptions::new().ignore("*.tmp").ignore("build");
+        assert_eq!(hash_tree_with_options(HashAlgorithm::SHA2_256, &tree_e, &options).unwrap(), digest_a);
+        let options = TreeOptions::new().ignore("sub/*.tmp").ignore("build/**");
+        assert_eq!(hash_tree_with_options(HashAlgorithm::SHA2_256, &tree_e, &options).unwrap(), digest_a);
+        let options = TreeOptions::new().ignore("*.tmp").ignore("build").ignore("deeper/c.txt").ignore("*/c.txt");
+        assert_eq!(hash_tree_with_options(HashAlgorithm::SHA2_256, &tree_e, &options).unwrap(), digest_a);
+        assert!(hash_tree_with_options(HashAlgorithm::SHA2_256, &tree_e, &TreeOptions::new().ignore("[")).is_err());
+
+        for dir in [tree_a, tree_b, tree_c, tree_d, tree_e].iter() {
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hash_tree_unix_options() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let files = [("run.sh", "#!/bin/sh"), ("data/values.txt", "1 2 3")];
+        let tree = make_tree("estahr_tree_unix", &files);
+        let plain = hash_tree(HashAlgorithm::BLAKE3, &tree).unwrap();
+
+        std::fs::set_permissions(tree.join("run.sh"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(hash_tree(HashAlgorithm::BLAKE3, &tree).unwrap(), plain);
+        let with_exec = TreeOptions::new().executable_bit(true);
+        assert_ne!(hash_tree_with_options(HashAlgorithm::BLAKE3, &tree, &with_exec).unwrap(), plain);
+
+        symlink("data", tree.join("link")).unwrap();
+        let with_link = hash_tree(HashAlgorithm::BLAKE3, &tree).unwrap();
+        assert_ne!(with_link, plain);
+        let followed = hash_tree_with_options(HashAlgorithm::BLAKE3, &tree, &TreeOptions::new().follow_symlinks(true)).unwrap();
+        assert_ne!(followed, with_link);
+
+        symlink("..", tree.join("data/up")).unwrap();
+        assert!(hash_tree_with_options(HashAlgorithm::BLAKE3, &tree, &TreeOptions::new().follow_symlinks(true)).is_err());
+        std::fs::remove_dir_all(&tree).unwrap();
+
+        // Names that are not valid UTF-8 are hashed as raw bytes, so they do not collide.
+        use std::os::unix::ffi::OsStrExt;
+        let mut digests = Vec::new();
+        for name in [&b"\xff"[..], &b"\xfe"[..]].iter() {
+            let tree = make_tree("estahr_tree_non_utf8", &[]);
+            std::fs::create_dir_all(&tree).unwrap();
+            std::fs::write(tree.join(std::ffi::OsStr::from_bytes(name)), "same").unwrap();
+            digests.push(hash_tree(HashAlgorithm::BLAKE3, &tree).unwrap());
+            std::fs::remove_dir_all(&tree).unwrap();
+        }
+        assert_ne!(digests[0], digests[1]);
+    }
+
+    #[test]
+    fn test_hash_files() {
+        let small = get_tests_file_path_string(FILENAME_SMALL);
+        let large = get_tests_file_path_string(FILENAME_LARGE);
+        let mut paths = Vec::new();
+        for i in 0..50 {
+            paths.push(if i % 2 == 0 { small.clone() } else { large.clone() });
+        }
+        paths.insert(7, "does_not_exist.txt".to_string());
+
+        let results: Vec<_> = hash_files_with_options(HashAlgorithm::MD5, &paths, &BatchOptions::new().threads(3)).collect();
+        assert_eq!(results.len(), paths.len());
+        for ((path, result), expected) in results.iter().zip(paths.iter()) {
+            assert_eq!(path.to_str().unwrap(), expected);
+            if *expected == small {
+                assert_eq!(result.as_ref().unwrap().to_hex(), "bc3865fa3378733b0a0a665a2d943595");
+            } else if *expected == large {
+                assert_eq!(result.as_ref().unwrap().to_hex(), "658528b6652f8eca8b1321af88ec2bcf");
+            } else {
+                assert!(result.is_err());
+            }
+        }
+
+        let unordered = hash_files_with_options(HashAlgorithm::MD5, &paths, &BatchOptions::new().ordered(false));
+        let mut failures = 0;
+        let mut count = 0;
+        for (_, result) in unordered {
+            count += 1;
+            if result.is_err() {
+                failures += 1;
+            }
+        }
+        assert_eq!((count, failures), (paths.len(), 1));
+        assert_eq!(hash_files(HashAlgorithm::MD5, Vec::<String>::new()).count(), 0);
+        assert_eq!(hash_files(HashAlgorithm::MD5, &paths).take(2).count(), 2);
+    }
+
+    #[test]
+    fn test_checksum_file() {
+        let cases = [
+            (ChecksumAlgorithm::XXH3_64, "5e5a8f300afc781d", "41c02551f6e895d4"),
+            (ChecksumAlgorithm::XXH3_128, "08db8cda3f24bd095e5a8f300afc781d", "2687fde65d373b7541c02551f6e895d4"),
+            (ChecksumAlgorithm::XXH64, "06ce7920824f07aa", "498ab6f65ef57499"),
+            (ChecksumAlgorithm::CRC32, "d39be353", "6632bb95"),
+            (ChecksumAlgorithm::CRC32C, "67e212ac", "ad3fb5cd"),
+            (ChecksumAlgorithm::CRC64, "7939e495563621f0", "126160e0a03db2d1"),
+            (ChecksumAlgorithm::ADLER32, "de66ca22", "34764582"),
+            (ChecksumAlgorithm::FNV1A32, "08e086ad", "4477cb57"),
+            (ChecksumAlgorithm::FNV1A64, "d626080378af0ead", "1690ed7cebf52237"),
+            (ChecksumAlgorithm::MURMUR3_32, "63b0168f", "943ad8c6"),
+            (ChecksumAlgorithm::MURMUR3_128, "d3538905f33b5945fb645cb61dc39a8a", "db49bdc7fab309db4aa9313074febe55"),
+        ];
+        for &(algorithm, small, large) in cases.iter() {
+            assert_eq!(checksum_file(algorithm, get_tests_file_path_string(FILENAME_SMALL)).unwrap().to_hex(), small);
+            assert_eq!(checksum_file(algorithm, get_tests_file_path_string(FILENAME_LARGE)).unwrap().to_hex(), large);
+
+            let content = std::fs::read(get_tests_file_path_string(FILENAME_LARGE)).unwrap();
+            assert_eq!(checksum_reader(algorithm, &content[..]).unwrap().to_hex(), large);
+        }
+        assert!(checksum_file(ChecksumAlgorithm::CRC32, std::env::temp_dir().join("estahr_missing_file")).is_err());
+    }
+
+    #[test]
+    fn test_verify_file() {
+        let small = get_tests_file_path_string(FILENAME_SMALL);
+        let large = get_tests_file_path_string(FILENAME_LARGE);
+        let sha256 = "sha256-xnU0PrP5Mxdy2Y8B544PfhLpd/TBIgDfWZkGQA0Y5bc=";
+        let sha384 = "sha384-keCn92Di91orfrrSNwXuGtUtdNnqV0sR8y3ny2wlQLJqn+zCazp6tV/7YoADKPkp";
+
+        assert!(verify_file(&small, sha256).unwrap());
+        assert!(!verify_file(&large, sha256).unwrap());
+        assert!(verify_file(&small, "md5:bc3865fa3378733b0a0a665a2d943595").unwrap());
+        assert!(verify_file(&large, "md5:658528b6652f8eca8b1321af88ec2bcf").unwrap());
+        let multihash = hash_file(HashAlgorithm::BLAKE3, &small).unwrap().to_multihash().unwrap();
+        assert!(verify_file(&small, &hex_string(&multihash)).unwrap());
+
+        // Only the strongest algorithm of an integrity attribute counts.
+        assert!(verify_file(&small, &format!("{} {}", sha256, sha384)).unwrap());
+        let wrong_sha384 = "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO";
+        assert!(!verify_file(&small, &format!("{} {}", sha256, wrong_sha384)).unwrap());
+        assert!(verify_file(&small, &format!("{} {}", wrong_sha384, sha384)).unwrap());
+
+        // A weaker digest never decides the result, whatever the order.
+        let small_md5 = "md5:bc3865fa3378733b0a0a665a2d943595";
+        let wrong_sha256 = "sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+        assert!(!verify_file(&small, &format!("{} {}", wrong_sha256, small_md5)).unwrap());
+        assert!(!verify_file(&small, &format!("{} {}", small_md5, wrong_sha256)).unwrap());
+        assert!(verify_file(&small, &format!("{} md5:658528b6652f8eca8b1321af88ec2bcf", sha256)).unwrap());
+        assert!(verify_file(&small, &format!("md5:658528b6652f8eca8b1321af88ec2bcf {}", sha256)).unwrap());
+
+        let error = verify_file(&small, "sha4:00").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(verify_file(&small, " ").unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    fn hex_string(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 32) as u8
+        }).collect()
+    }
+
+    fn collect_chunks(data: &[u8], options: &ChunkerOptions) -> Vec<(u64, usize, Digest)> {
+        chunking::chunk_reader(data, options).unwrap().map(|chunk| chunk.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_chunking() {
+        let data = random_bytes(1_000_000, 0x2545_f491_4f6c_dd1d);
+        for &algorithm in [ChunkerAlgorithm::FastCdc, ChunkerAlgorithm::Buzhash].iter() {
+            let options = ChunkerOptions::new()
+                .algorithm(algorithm)
+                .min_size(1024)
+                .avg_size(4096)
+                .max_size(16 * 1024)
+                .hash_algorithm(HashAlgorithm::SHA2_256);
+            let chunks = collect_chunks(&data, &options);
+
+            let mut offset = 0;
+            for (i, (chunk_offset, length, digest)) in chunks.iter().enumerate() {
+                assert_eq!(*chunk_offset, offset as u64);
+                assert!(*length <= 16 * 1024);
+                assert!(*length >= 1024 || i == chunks.len() - 1);
+                assert_eq!(*digest, hash_bytes(HashAlgorithm::SHA2_256, &data[offset..offset + length]));
+                offset += length;
+            }
+            assert_eq!(offset, data.len());
+            let average = data.len() / chunks.len();
+            assert!(average > 2048 && average < 8192, "{:?} average chunk size {}", algorithm, average);
+
+            // Inserting bytes near the start only changes the chunks around the insertion.
+            let mut edited = data[..5000].to_vec();
+            edited.extend_from_slice(b"inserted bytes");
+            edited.extend_from_slice(&data[5000..]);
+            let edited_chunks = collect_chunks(&edited, &options);
+            let shared = edited_chunks.iter()
+                .filter(|(_, _, digest)| chunks.iter().any(|(_, _, original)| original == digest))
+                .count();
+            assert!(shared + 3 >= chunks.len(), "{:?} only shares {} of {} chunks", algorithm, shared, chunks.len());
+
+            // The chunks do not depend on how the reader splits its reads.
+            let reader = std::io::BufReader::with_capacity(100, &data[..]);
+            let from_small_reads: Vec<_> = chunking::chunk_reader(reader, &options).unwrap().map(|c| c.unwrap()).collect();
+            assert_eq!(from_small_reads, chunks);
+        }
+
+        assert_eq!(collect_chunks(&[], &ChunkerOptions::new()), vec![]);
+        // The buffer grows with the input, so a huge maximum size does not allocate it.
+        let unbounded = ChunkerOptions::new().max_size(usize::MAX);
+        assert_eq!(collect_chunks(&data[..10], &unbounded), vec![(0, 10, hash_bytes(HashAlgorithm::BLAKE3, &data[..10]))]);
+        let invalid = ChunkerOptions::new().min_size(10_000).avg_size(100);
+        assert_eq!(chunking::chunk_reader(&data[..], &invalid).err().unwrap().kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_chunking_known_answers() {
+        // These boundaries must never change: stored chunks would no longer deduplicate.
+        let data = random_bytes(16_000, 42);
+        let expected: [(ChunkerAlgorithm, &[(u64, usize)]); 2] = [
+            (ChunkerAlgorithm::FastCdc, &[(0, 1202), (1202, 1609), (2811, 936), (3747, 1242), (4989, 675), (5664, 1718),
+                                          (7382, 1253), (8635, 1373), (10008, 755), (10763, 1059), (11822, 2232),
+                                          (14054, 997), (15051, 763), (15814, 186)]),
+            (ChunkerAlgorithm::Buzhash, &[(0, 853), (853, 1956), (2809, 460), (3269, 3171), (6440, 2077), (8517, 357),
+                                          (8874, 305), (9179, 1834), (11013, 2302), (13315, 2328), (15643, 357)]),
+        ];
+        for (algorithm, boundaries) in expected.iter() {
+            let options = ChunkerOptions::new().algorithm(*algorithm).min_size(256).avg_size(1024).max_size(4096);
+            let chunks: Vec<_> = collect_chunks(&data, &options).into_iter().map(|(offset, length, _)| (offset, length)).collect();
+            assert_eq!(chunks, *boundaries, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_chunk_file() {
+        let options = ChunkerOptions::new().min_size(64).avg_size(256).max_size(1024).hash_algorithm(HashAlgorithm::MD5);
+        let chunks: Vec<_> = chunking::chunk_file(get_tests_file_path_string(FILENAME_LARGE), &options).unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect();
+        let content = std::fs::read(get_tests_file_path_string(FILENAME_LARGE)).unwrap();
+        assert_eq!(chunks.iter().map(|(_, length, _)| length).sum::<usize>(), content.len());
+        assert_eq!(chunks, collect_chunks(&content, &options));
+        assert!(chunking::chunk_file(std::env::temp_dir().join("estahr_missing_file"), &options).is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_hash_async_reader() {
+        let path = get_tests_file_path_string(FILENAME_LARGE);
+        for &algorithm in HashAlgorithm::all() {
+            let file = tokio::fs::File::open(&path).await.unwrap();
+            assert_eq!(hash_async_reader(algorithm, file).await.unwrap(), hash_file(algorithm, &path).unwrap());
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_hashing_writer() {
+        use tokio::io::AsyncWriteExt;
+
+        let data = random_bytes(300_000, 21);
+        let mut writer = AsyncHashingWriter::new(HashAlgorithm::SHA3_256, Vec::new());
+        for piece in data.chunks(7777) {
+            writer.write_all(piece).await.unwrap();
+        }
+        writer.flush().await.unwrap();
+        assert_eq!(writer.finalize(), hash_bytes(HashAlgorithm::SHA3_256, &data));
+        assert_eq!(writer.get_ref().len(), data.len());
+        let (copy, digest) = writer.into_inner();
+        assert_eq!(copy, data);
+        assert_eq!(digest, hash_bytes(HashAlgorithm::SHA3_256, &data));
+
+        let mut sink = AsyncHashingWriter::new(HashAlgorithm::MD5, tokio::io::sink());
+        let copied = tokio::io::copy(&mut &data[..], &mut sink).await.unwrap();
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(sink.finalize(), hash_bytes(HashAlgorithm::MD5, &data));
+    }
+
+    #[test]
+    fn test_hashing_reader() {
+        use std::io::Read;
+
+        let path = get_tests_file_path_string(FILENAME_LARGE);
+        let expected = hash_file(HashAlgorithm::SHA2_512, &path).unwrap();
+        let mut reader = HashingReader::new(HashAlgorithm::SHA2_512, std::fs::File::open(&path).unwrap())
+            .expect(expected.clone());
+        let mut copy = Vec::new();
+        reader.read_to_end(&mut copy).unwrap();
+        assert_eq!(copy, std::fs::read(&path).unwrap());
+        assert_eq!(reader.finalize(), expected);
+        let (_, digest) = reader.into_inner();
+        assert_eq!(digest, expected);
+
+        // The mismatch is reported by the read that reaches EOF, after all the data.
+        let data = random_bytes(100_000, 22);
+        let wrong = hash_bytes(HashAlgorithm::SHA2_512, b"something else");
+        let mut reader = HashingReader::new(HashAlgorithm::SHA2_512, &data[..]).expect(wrong);
+        let mut copy = Vec::new();
+        let error = reader.read_to_end(&mut copy).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(copy, data);
+        let other_algorithm = hash_bytes(HashAlgorithm::SHA3_512, &data);
+        let mut reader = HashingReader::new(HashAlgorithm::SHA2_512, &data[..]).expect(other_algorithm);
+        assert!(std::io::copy(&mut reader, &mut std::io::sink()).is_err());
+    }
+
+    #[test]
+    fn test_hashing_writer() {
+        use std::io::Write;
+
+        let data = random_bytes(250_000, 23);
+        let expected = hash_bytes(HashAlgorithm::BLAKE2B, &data);
+        let mut writer = HashingWriter::new(HashAlgorithm::BLAKE2B, Vec::new()).expect(expected.clone());
+        for piece in data.chunks(1000) {
+            writer.write_all(piece).unwrap();
+        }
+        assert_eq!(writer.get_ref().len(), data.len());
+        assert_eq!(writer.finalize(), expected);
+        let (copy, digest) = writer.finish().unwrap();
+        assert_eq!(copy, data);
+        assert_eq!(digest, expected);
+
+        let mut writer = HashingWriter::new(HashAlgorithm::BLAKE2B, Vec::new())
+            .expect(hash_bytes(HashAlgorithm::BLAKE2B, b"other"));
+        writer.write_all(&data).unwrap();
+        assert_eq!(writer.finish().unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+        // Only the bytes the wrapped writer accepts are hashed.
+        let mut buffer = [0u8; 10];
+        let mut writer = HashingWriter::new(HashAlgorithm::SHA2_256, &mut buffer[..]);
+        assert_eq!(writer.write(b"0123456789abcdef").unwrap(), 10);
+        assert_eq!(writer.finalize(), hash_string(HashAlgorithm::SHA2_256, "0123456789"));
+        assert!(writer.write_all(b"more").is_err());
+    }
+
+    #[test]
+    fn test_hash_file_progress_and_stats() {
+        use std::sync::{Arc, Mutex};
+
+        let data = random_bytes(17 * 1024 * 1024 + 123, 24);
+        let mut path = std::env::temp_dir();
+        path.push("estahr_hash_file_with_stats.bin");
+        std::fs::write(&path, &data).unwrap();
+
+        // Streamed and memory mapped, the progress goes up to the size of the file.
+        for &(algorithm, mmap) in [(HashAlgorithm::MD5, false), (HashAlgorithm::BLAKE3, true)].iter() {
+            let reports = Arc::new(Mutex::new(Vec::new()));
+            let sink = Arc::clone(&reports);
+            let options = HashOptions::new()
+                .buffer_size(1024 * 1024)
+                .mmap(mmap)
+                .progress(move |done, total| sink.lock().unwrap().push((done, total)));
+            let report = hash_file_with_stats(algorithm, &path, &options).unwrap();
+            assert_eq!(report.digest, hash_bytes(algorithm, &data));
+            assert_eq!(report.bytes_read, data.len() as u64);
+            assert!(report.throughput() > 0.0);
+
+            let reports = reports.lock().unwrap();
+            assert!(reports.len() >= 2);
+            assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            assert!(reports.iter().all(|&(_, total)| total == Some(data.len() as u64)));
+            assert_eq!(reports.last().unwrap().0, data.len() as u64);
+        }
+
+        // A token cancelled before or during the hashing stops it.
+        for &mmap in [false, true].iter() {
+            let token = CancelToken::new();
+            token.cancel();
+            let options = HashOptions::new().mmap(mmap).cancel_token(token);
+            assert!(hash_file_with_options(HashAlgorithm::BLAKE3, &path, &options).is_err());
+
+            let token = CancelToken::new();
+            let canceller = token.clone();
+            let calls = Arc::new(Mutex::new(0));
+            let counter = Arc::clone(&calls);
+            let options = HashOptions::new()
+                .mmap(mmap)
+                .progress(move |_, _| {
+                    *counter.lock().unwrap() += 1;
+                    canceller.cancel();
+                })
+                .cancel_token(token.clone());
+            assert!(hash_file_with_stats(HashAlgorithm::BLAKE3, &path, &options).is_err());
+            assert!(token.is_cancelled());
+            assert_eq!(*calls.lock().unwrap(), 1);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_hash_file_range() {
+        let path = get_tests_file_path_string(FILENAME_LARGE);
+        let data = std::fs::read(&path).unwrap();
+        for &(offset, length) in [(0, data.len()), (0, 0), (100, 500), (data.len() - 1, 1), (data.len(), 0)].iter() {
+            assert_eq!(hash_file_range(HashAlgorithm::SHA2_256, &path, offset as u64, length as u64).unwrap(),
+                       hash_bytes(HashAlgorithm::SHA2_256, &data[offset..offset + length]));
+        }
+        let error = hash_file_range(HashAlgorithm::SHA2_256, &path, 100, data.len() as u64).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(hash_file_range(HashAlgorithm::SHA2_256, std::env::temp_dir().join("estahr_missing_file"), 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_hash_file_sampled() {
+        let data = random_bytes(1_000_000, 25);
+        let mut path = std::env::temp_dir();
+        path.push("estahr_hash_file_sampled.bin");
+        std::fs::write(&path, &data).unwrap();
+
+        let sampled = |blocks: usize, block_size: usize| hash_file_sampled(HashAlgorithm::BLAKE3, &path, blocks, block_size).unwrap().to_hex();
+        let expected = |pieces: &[&[u8]]| {
+            let mut hasher = Hasher::new(HashAlgorithm::BLAKE3);
+            hasher.update(&(data.len() as u64).to_le_bytes());
+            pieces.iter().for_each(|piece| hasher.update(piece));
+            hasher.finalize().to_hex()
+        };
+        // The start, middle and end blocks.
+        assert_eq!(sampled(3, 1000), expected(&[&data[..1000], &data[499_500..500_500], &data[999_000..]]));
+        assert_eq!(hash_file_sampled(HashAlgorithm::SHA2_256, &path, 3, 1000).unwrap().algorithm(), HashAlgorithm::SHA2_256);
+        assert_eq!(sampled(2, 10), expected(&[&data[..10], &data[999_990..]]));
+        assert_eq!(sampled(1, 10), expected(&[&data[..10]]));
+        for &(blocks, block_size) in [(0, 10), (0, 2_000_000), (3, 0)].iter() {
+            let error = hash_file_sampled(HashAlgorithm::BLAKE3, &path, blocks, block_size).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        }
+        // Small files are hashed whole.
+        assert_eq!(sampled(4, 250_000), expected(&[&data]));
+        assert_eq!(sampled(100, 1_000_000), expected(&[&data]));
+
+        // A change in a sampled block is detected, a change elsewhere is not.
+        let mut changed = data.clone();
+        changed[500_000] ^= 1;
+        std::fs::write(&path, &changed).unwrap();
+        assert_ne!(sampled(3, 1000), expected(&[&data[..1000], &data[499_500..500_500], &data[999_000..]]));
+        let mut changed = data.clone();
+        changed[200_000] ^= 1;
+        std::fs::write(&path, &changed).unwrap();
+        assert_eq!(sampled(3, 1000), expected(&[&data[..1000], &data[499_500..500_500], &data[999_000..]]));
+        // So is a change of size.
+        std::fs::write(&path, &data[..999_999]).unwrap();
+        assert_ne!(hash_file_sampled(HashAlgorithm::BLAKE3, &path, 1, 10).unwrap().to_hex(), expected(&[&data[..10]]));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_merkle_tree_from_file() {
+        let path = get_tests_file_path_string(FILENAME_LARGE);
+        let data = std::fs::read(&path).unwrap();
+        let tree = MerkleTree::from_file(HashAlgorithm::SHA2_256, &path, 100).unwrap();
+        assert_eq!(tree, MerkleTree::from_leaves(HashAlgorithm::SHA2_256, data.chunks(100)));
+        assert_eq!(tree.len(), data.len().div_ceil(100));
+
+        let whole = MerkleTree::from_file(HashAlgorithm::BLAKE3, &path, data.len()).unwrap();
+        assert_eq!(whole.len(), 1);
+        assert_eq!(MerkleTree::from_reader(HashAlgorithm::BLAKE3, &b""[..], 16).unwrap().len(), 0);
+        // Short reads must not split blocks.
+        let chunked = MerkleTree::from_reader(HashAlgorithm::SHA2_256, std::io::Read::chain(&data[..7], &data[7..]), 100).unwrap();
+        assert_eq!(chunked, tree);
+
+        let proof = tree.inclusion_proof(3).unwrap();
+        assert!(proof.verify(&data[300..400], &tree.root()));
+
+        assert_eq!(MerkleTree::from_file(HashAlgorithm::SHA2_256, &path, 0).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert!(MerkleTree::from_file(HashAlgorithm::SHA2_256, std::env::temp_dir().join("estahr_missing_file"), 100).is_err());
     }
 }
\ No newline at end of file